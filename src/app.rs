@@ -0,0 +1,1096 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{
+    DateTime, Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use reqwest::Url;
+use serde::Serialize;
+#[cfg(feature = "image")]
+use webuntis::timetable_image::TimetableRenderer;
+use webuntis::{
+    AssignmentKeywords, Credentials, DateFormat, LessonChange, LessonInfo, TeacherMatch, TlsConfig,
+    diff_lessons, diff_lessons_since,
+    discord::{DiscordClient, Forum, Identities, LessonField, NotifyStyle, Template},
+    extract_all_lessons, extract_all_lessons_lenient, merge_double_periods,
+    notify::{
+        FallbackNotifier, Notifier, RecentNotifications, RecordingNotifier, StdoutNotifier,
+        ThrottledNotifier,
+    },
+    send_diffs_resumable, send_potential_diffs,
+    untis::{
+        HostAllowlist, Maintenance, UntisClient,
+        entries::{Day, ResourceType, Status, Timetable, expected_format_version},
+        exams::{Exam, diff_exams},
+        messages::DayMessage,
+        resources::Resource,
+        time_grid::TimeGrid,
+    },
+    webhook::GenericWebhookClient,
+};
+
+use crate::{
+    config::WatchConfig,
+    live_message::LiveMessage,
+    resource_cache::ResourceCache,
+    state_store::{SavedDays, StateStore},
+};
+
+/// A watch is disabled after failing this many times in a row.
+const MAX_SEQUENTIAL_ERRORS: u32 = 5;
+
+/// Whether a warning about a changed API format was already sent (by any watch).
+static FORMAT_WARNING_SENT: AtomicBool = AtomicBool::new(false);
+
+/// How often the exams are checked (if enabled), since they rarely change.
+const EXAM_CHECK_INTERVAL: Duration = Duration::from_mins(10);
+
+/// How many days ahead exams are checked.
+const EXAM_LOOKAHEAD_DAYS: u64 = 28;
+
+/// How often the messages of the day are checked (if enabled).
+const MESSAGE_CHECK_INTERVAL: Duration = Duration::from_mins(10);
+
+/// How often the look-ahead range is checked for newly published days (if enabled).
+const PUBLISH_CHECK_INTERVAL: Duration = Duration::from_hours(24);
+
+/// Warn if the local clock is off by more than this, since it might pick the wrong day.
+const MAX_CLOCK_SKEW: TimeDelta = TimeDelta::minutes(5);
+
+/// Which version of the day's timetable new fetches are compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Baseline {
+    /// Compare against the previous fetch, starting over after every change.
+    Rolling,
+    /// Compare against the first fetch of the day, reporting only net changes.
+    Sticky,
+}
+
+/// What to do with the first timetable fetched for each day after starting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StartupMode {
+    /// Take it as the baseline without notifying.
+    Silent,
+    /// Send the whole timetable once.
+    Announce,
+    /// Report how it differs from the timetable seen by the previous run.
+    Diff,
+}
+
+/// Watching more days than this at once is most likely a mistake.
+const MAX_WATCH_DAYS: u64 = 31;
+
+/// Which days to watch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchRange {
+    /// Today, or tomorrow in the evening.
+    Relevant,
+    /// The relevant day plus this many days after it.
+    DaysAhead(u64),
+    /// A fixed range of dates (inclusive).
+    Fixed(NaiveDate, NaiveDate),
+}
+
+impl WatchRange {
+    /// A fixed range of dates, checking that it's ordered and not unreasonably long.
+    pub fn fixed(start: NaiveDate, end: NaiveDate) -> Result<Self> {
+        if end < start {
+            bail!("Watch range ends ({end}) before it starts ({start})");
+        }
+        let days = end.signed_duration_since(start).num_days().unsigned_abs() + 1;
+        if days > MAX_WATCH_DAYS {
+            bail!("Watch range spans {days} days; at most {MAX_WATCH_DAYS} are allowed");
+        }
+        Ok(Self::Fixed(start, end))
+    }
+
+    /// The first and last (inclusive) date to watch.
+    fn dates(self, now: DateTime<Tz>) -> (NaiveDate, NaiveDate) {
+        match self {
+            Self::Relevant => {
+                let date = get_relevant_date(now);
+                (date, date)
+            }
+            Self::DaysAhead(days) => {
+                let date = get_relevant_date(now);
+                let end = date.checked_add_days(Days::new(days)).unwrap_or(date);
+                (date, end)
+            }
+            Self::Fixed(start, end) => (start, end),
+        }
+    }
+}
+
+pub struct App {
+    watches: Vec<Watch>,
+    /// How long to sleep between iterations outside of school hours.
+    off_hours_sleep: Duration,
+}
+
+impl App {
+    #[must_use]
+    pub const fn new(watches: Vec<Watch>, off_hours_sleep: Duration) -> Self {
+        Self {
+            watches,
+            off_hours_sleep,
+        }
+    }
+
+    /// Run all watches until every single one of them failed too many times in a row, or until
+    /// interrupted (Ctrl-C or SIGTERM), logging out of every watch then.
+    pub fn run(mut self) -> Result<()> {
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        ctrlc::set_handler(move || {
+            // Only fails if the loop already stopped
+            let _ = stop_sender.send(());
+        })
+        .context("Could not install the Ctrl-C handler")?;
+
+        loop {
+            let now: DateTime<Utc> = Utc::now();
+            for watch in &mut self.watches {
+                watch.iteration_logged();
+            }
+
+            self.watches.retain_mut(|watch| {
+                if watch.sequential_errors < MAX_SEQUENTIAL_ERRORS {
+                    return true;
+                }
+                watch
+                    .notifier
+                    .send_error("Watch failed too many times in a row; disabling it.");
+                watch.logout();
+                false
+            });
+            if self.watches.is_empty() {
+                bail!("All watches failed {MAX_SEQUENTIAL_ERRORS} times in a row");
+            }
+
+            let dur = get_sleep_time(now, self.off_hours_sleep);
+            if stop_receiver.recv_timeout(dur).is_ok() {
+                break;
+            }
+        }
+
+        log::info!("Interrupted; logging out of every watch.");
+        for watch in &mut self.watches {
+            watch.logout();
+        }
+        Ok(())
+    }
+}
+
+/// Settings from the command line shared by all watches.
+pub struct WatchOptions {
+    pub forum: Option<Forum>,
+    pub lesson_fields: Vec<LessonField>,
+    pub notify_style: NotifyStyle,
+    /// Prefixed to the title of every Discord message.
+    pub label: Option<String>,
+    /// Usernames and avatars per change kind.
+    pub identities: Identities,
+    pub coalesce_window: Duration,
+    pub webhook_timeout: Duration,
+    /// How long a session is used before logging in again.
+    pub relogin_interval: Duration,
+    /// Identical errors within this window are only sent once.
+    pub error_repeat_window: Duration,
+    /// A Discord webhook to send to whenever the watch's own notifier fails.
+    pub fallback_webhook_url: Option<Url>,
+    /// Print changes to stdout, whatever notification targets the watch configures.
+    pub stdout: bool,
+    /// An iteration still running after this long is aborted (zero = no limit).
+    pub iteration_timeout: Duration,
+    pub baseline: Baseline,
+    pub tls: TlsConfig,
+    pub allowed_hosts: HostAllowlist,
+    pub resource_cache: Rc<ResourceCache>,
+    pub deep_link: bool,
+    pub title_template: Template,
+    pub body_template: Template,
+    pub date_format: DateFormat,
+    pub range: WatchRange,
+    pub resource_type: ResourceType,
+    pub lenient_extract: bool,
+    pub skip_past_lessons: bool,
+    pub teacher_match: TeacherMatch,
+    pub assignment_keywords: AssignmentKeywords,
+    pub no_school_statuses: HashSet<Status>,
+    /// Instead of sending changes as they happen, send one digest per day at this time.
+    pub digest_at: Option<NaiveTime>,
+    /// While this file exists, changes are tracked but not sent.
+    pub pause_file: Option<PathBuf>,
+    /// Only days on these weekdays are checked (all if empty).
+    pub notify_weekdays: HashSet<Weekday>,
+    pub merge_double_periods: bool,
+    pub notify_exams: bool,
+    pub notify_messages: bool,
+    /// How many days ahead to look for newly published timetables, if at all.
+    pub publish_lookahead_days: Option<u64>,
+    /// Where to remember sent notifications for the status server, if it runs.
+    pub recent: Option<RecentNotifications>,
+    pub on_startup: StartupMode,
+    /// Renders the image attached to timetable messages, if wanted.
+    #[cfg(feature = "image")]
+    pub timetable_image: Option<TimetableRenderer>,
+    /// Where the last seen lessons are kept between runs (only for [`StartupMode::Diff`]).
+    pub state_store: Option<Rc<StateStore>>,
+    /// Where the IDs of live timetable messages are kept, if the bot maintains one.
+    pub live_message_file: Option<PathBuf>,
+}
+
+/// What was previously seen of a single day, to compare new fetches against.
+#[derive(Default)]
+struct DayState {
+    prev_lessons: Option<Vec<LessonInfo>>,
+    /// The lessons as of the last notification (only in sticky baseline mode).
+    reported_lessons: Option<Vec<LessonInfo>>,
+    /// When the first not-yet-sent change was detected (if coalescing).
+    pending_since: Option<Instant>,
+    /// Whether the day's digest was already sent (only in digest mode).
+    digest_sent: bool,
+    /// The changes already sent of a diff whose delivery failed partway, so retrying it doesn't
+    /// send them again.
+    delivered: Vec<LessonChange>,
+}
+
+/// A single school account/timetable being watched, with its own session and baseline.
+pub struct Watch {
+    name: String,
+    notifier: Box<dyn Notifier>,
+    untis_client: Option<UntisClient>,
+    credentials: Credentials,
+    last_login: Instant,
+    /// A new session is created once the current one is this old.
+    relogin_interval: Duration,
+    timetable_id: Option<i32>,
+    class: Option<String>,
+    timezone: Option<Tz>,
+    /// A week of lessons to report deviations from, instead of changes between fetches.
+    reference: Option<Vec<LessonInfo>>,
+    /// The bell schedule, to show period numbers (if the school provides it).
+    time_grid: Option<TimeGrid>,
+    range: WatchRange,
+    /// Whether a class's or a teacher's timetable is watched.
+    resource_type: ResourceType,
+    /// The diffing state of every watched day.
+    days: BTreeMap<NaiveDate, DayState>,
+    baseline: Baseline,
+    coalesce_window: Duration,
+    /// An iteration still running after this long is aborted between steps (zero = no limit).
+    iteration_timeout: Duration,
+    /// When the current iteration started.
+    iteration_start: Instant,
+    /// Whether the local clock was already compared against the Untis server's.
+    clock_checked: bool,
+    tls: TlsConfig,
+    allowed_hosts: HostAllowlist,
+    resource_cache: Rc<ResourceCache>,
+    /// Whether to link notifications to the timetable in the WebUntis web client.
+    deep_link: bool,
+    /// Whether to skip grid entries that could not be extracted instead of failing.
+    lenient_extract: bool,
+    /// Whether to stay silent about lessons that are already over.
+    skip_past_lessons: bool,
+    /// Which of the teacher's names decides whether the teacher changed.
+    teacher_match: TeacherMatch,
+    /// Notes and substitution texts mentioning any of these are reported as assignments.
+    assignment_keywords: AssignmentKeywords,
+    /// Days with any of these statuses are not school days and are not diffed.
+    no_school_statuses: HashSet<Status>,
+    /// Send one digest per day at this time instead of every change as it happens.
+    digest_at: Option<NaiveTime>,
+    pause_file: Option<PathBuf>,
+    /// Only days on these weekdays are checked (all if empty).
+    notify_weekdays: HashSet<Weekday>,
+    /// Whether to report double periods as a single lesson.
+    merge_double_periods: bool,
+    notify_exams: bool,
+    /// The exams seen by the last check and the end of its window (only if notifying about exams).
+    exams: Option<(NaiveDate, Vec<Exam>)>,
+    exams_checked_at: Option<Instant>,
+    notify_messages: bool,
+    /// The IDs of all messages of the day seen so far (only if notifying about them).
+    seen_messages: Option<HashSet<i32>>,
+    messages_checked_at: Option<Instant>,
+    publish_lookahead_days: Option<u64>,
+    /// The days in the look-ahead range that had no lessons at the last check.
+    empty_days: Option<BTreeSet<NaiveDate>>,
+    published_checked_at: Option<Instant>,
+    /// Whether the pause file existed in the previous iteration.
+    paused: bool,
+    on_startup: StartupMode,
+    state_store: Option<Rc<StateStore>>,
+    /// The lessons seen by the previous run (only for [`StartupMode::Diff`]).
+    saved_days: SavedDays,
+    /// A message showing the relevant day's timetable, kept up to date.
+    live_message: Option<LiveMessage>,
+    /// Whether an iteration succeeded since starting.
+    started: bool,
+    sequential_errors: u32,
+}
+
+impl Watch {
+    pub fn new(config: WatchConfig, options: &WatchOptions) -> Result<Self> {
+        let name = format!("{}@{}", config.username, config.school);
+        if options.resource_type != ResourceType::Class && config.class.is_some() {
+            bail!(
+                "{name}: only class timetables can be given by name; use a timetable ID (see list-resources)"
+            );
+        }
+        if options.resource_type != ResourceType::Class && options.notify_exams {
+            bail!("{name}: exams can only be watched for class timetables");
+        }
+        if config.discord_bot_token.is_some() && options.forum.is_some() {
+            bail!("{name}: forum threads can only be created through a webhook, not as a bot");
+        }
+        if config.discord_bot_token.is_none() && options.live_message_file.is_some() {
+            bail!("{name}: only a Discord bot can edit a live message; set a bot token");
+        }
+        let configure = |client: Result<DiscordClient>| -> Result<DiscordClient> {
+            let mut discord_client = client
+                .and_then(|client| client.with_tls(&options.tls))
+                .and_then(|client| client.with_timeout(options.webhook_timeout))
+                .with_context(|| format!("Could not create Discord Client for {name}"))?
+                .with_lesson_fields(options.lesson_fields.clone())
+                .with_style(options.notify_style)
+                .with_identities(options.identities.clone())
+                .with_templates(
+                    options.title_template.clone(),
+                    options.body_template.clone(),
+                )
+                .with_date_format(options.date_format.clone());
+            if let Some(label) = &options.label {
+                discord_client = discord_client.with_label(label.clone());
+            }
+            if let Some(forum) = &options.forum {
+                discord_client = discord_client.with_forum(forum.clone());
+            }
+            #[cfg(feature = "image")]
+            if let Some(renderer) = &options.timetable_image {
+                discord_client = discord_client.with_timetable_image(renderer.clone());
+            }
+            Ok(discord_client)
+        };
+        let discord_client = |url: Url| configure(DiscordClient::new(url));
+        let bot = config.discord_bot_token.zip(config.discord_channel_id);
+        let live_message: Option<LiveMessage> = match (&bot, &options.live_message_file) {
+            (Some((token, channel_id)), Some(path)) => Some(LiveMessage::new(
+                configure(DiscordClient::from_bot(token, *channel_id))?,
+                path.clone(),
+                name.clone(),
+            )),
+            _ => None,
+        };
+        let stdout =
+            StdoutNotifier::new(name.clone()).with_date_format(options.date_format.clone());
+        let notifier: Box<dyn Notifier> =
+            match (config.discord_webhook_url, bot, config.webhook_url) {
+                _ if options.stdout => Box::new(stdout),
+                (Some(url), _, _) => Box::new(discord_client(url)?),
+                (None, Some((token, channel_id)), _) => {
+                    Box::new(configure(DiscordClient::from_bot(&token, channel_id))?)
+                }
+                (None, None, Some(url)) => Box::new(
+                    GenericWebhookClient::new(url, &config.webhook_headers, &options.tls)
+                        .with_context(|| format!("Could not create Webhook Client for {name}"))?,
+                ),
+                // Without a webhook or bot, changes are printed to stdout instead.
+                (None, None, None) => Box::new(stdout),
+            };
+        let notifier: Box<dyn Notifier> = match &options.fallback_webhook_url {
+            Some(url) => Box::new(FallbackNotifier::new(
+                notifier,
+                Box::new(discord_client(url.clone())?),
+            )),
+            None => notifier,
+        };
+        let notifier: Box<dyn Notifier> = match &options.recent {
+            Some(recent) => Box::new(RecordingNotifier::new(
+                notifier,
+                name.clone(),
+                recent.clone(),
+            )),
+            None => notifier,
+        };
+        let notifier = Box::new(ThrottledNotifier::new(
+            notifier,
+            options.error_repeat_window,
+        ));
+
+        let reference: Option<Vec<LessonInfo>> = config
+            .reference_file
+            .as_deref()
+            .map(load_reference)
+            .transpose()?;
+        let saved_days: SavedDays = match &options.state_store {
+            Some(state_store) => state_store.load(&name).unwrap_or_else(|e| {
+                log::warn!("[{name}] Could not load the lessons of the previous run: {e:?}");
+                SavedDays::new()
+            }),
+            None => SavedDays::new(),
+        };
+
+        Ok(Self {
+            name,
+            notifier,
+            untis_client: None,
+            credentials: Credentials {
+                school: config.school,
+                username: config.username,
+                password: config.password,
+                server: config.server,
+            },
+            last_login: Instant::now(),
+            relogin_interval: options.relogin_interval,
+            timetable_id: config.timetable_id,
+            class: config.class,
+            timezone: config.timezone,
+            reference,
+            time_grid: None,
+            range: options.range,
+            resource_type: options.resource_type,
+            days: BTreeMap::new(),
+            baseline: options.baseline,
+            coalesce_window: options.coalesce_window,
+            iteration_timeout: options.iteration_timeout,
+            iteration_start: Instant::now(),
+            clock_checked: false,
+            tls: options.tls.clone(),
+            allowed_hosts: options.allowed_hosts.clone(),
+            resource_cache: Rc::clone(&options.resource_cache),
+            deep_link: options.deep_link,
+            lenient_extract: options.lenient_extract,
+            skip_past_lessons: options.skip_past_lessons,
+            teacher_match: options.teacher_match,
+            assignment_keywords: options.assignment_keywords.clone(),
+            no_school_statuses: options.no_school_statuses.clone(),
+            digest_at: options.digest_at,
+            pause_file: options.pause_file.clone(),
+            notify_weekdays: options.notify_weekdays.clone(),
+            merge_double_periods: options.merge_double_periods,
+            notify_exams: options.notify_exams,
+            exams: None,
+            exams_checked_at: None,
+            notify_messages: options.notify_messages,
+            seen_messages: None,
+            messages_checked_at: None,
+            publish_lookahead_days: options.publish_lookahead_days,
+            empty_days: None,
+            published_checked_at: None,
+            paused: false,
+            on_startup: options.on_startup,
+            state_store: options.state_store.clone(),
+            saved_days,
+            live_message,
+            started: false,
+            sequential_errors: 0,
+        })
+    }
+
+    /// Log in if there is no session yet or the current one expired.
+    ///
+    /// On the first login, this also resolves the class name and detects the timezone (if needed).
+    fn ensure_login_validity(&mut self) -> Result<()> {
+        if self.untis_client.is_some() && self.last_login.elapsed() < self.relogin_interval {
+            return Ok(());
+        }
+
+        if self.untis_client.is_some() {
+            log::info!(
+                "[{}] Max login time {:?} expired; creating new session.",
+                self.name,
+                self.relogin_interval
+            );
+            self.logout();
+        } else {
+            log::info!("[{}] Logging into Untis...", self.name);
+        }
+
+        let untis_client =
+            UntisClient::login_with_tls(&self.credentials, &self.tls, &self.allowed_hosts)
+                .context("Could not log into Untis")?;
+        self.last_login = Instant::now();
+
+        // Once running, the permission evidently exists.
+        if !self.started {
+            untis_client.check_timetable_access(self.resource_type)?;
+        }
+        if self.timetable_id.is_none()
+            && let Some(class) = &self.class
+        {
+            let resources: Vec<Resource> = self
+                .resource_cache
+                .timetables(&self.name, &untis_client)
+                .context("Could not fetch the list of classes")?;
+            let id =
+                Resource::resolve(&resources, class).context("Could not resolve class name")?;
+            self.timetable_id = Some(id);
+        }
+        if self.timezone.is_none() {
+            self.timezone = Some(detect_timezone(&untis_client));
+        }
+        if self.time_grid.is_none() {
+            self.time_grid = fetch_time_grid(&untis_client);
+        }
+
+        self.untis_client = Some(untis_client);
+        Ok(())
+    }
+
+    /// End the current session (if any), so it doesn't count against the school's session limit.
+    fn logout(&mut self) {
+        let Some(untis_client) = self.untis_client.take() else {
+            return;
+        };
+        if let Err(e) = untis_client.logout() {
+            log::warn!("[{}] Could not log out of Untis: {e:?}", self.name);
+        }
+    }
+
+    /// Run one iteration, reporting (and counting) any error.
+    fn iteration_logged(&mut self) {
+        match self.iteration() {
+            Ok(()) => self.sequential_errors = 0,
+            // Maintenance ends by itself; it's neither worth a notification nor disabling the watch.
+            Err(e) if e.chain().any(|x| x.is::<Maintenance>()) => {
+                log::warn!("[{}] {e:#}; trying again later.", self.name);
+            }
+            Err(e) => {
+                let e = format!("[{}] {e:?}", self.name);
+                self.notifier.send_error(&e);
+                self.sequential_errors += 1;
+            }
+        }
+    }
+
+    /// Fail if the current iteration exceeded its time budget, instead of starting another step.
+    fn check_time_budget(&self, step: &str) -> Result<()> {
+        let elapsed: Duration = self.iteration_start.elapsed();
+        if !self.iteration_timeout.is_zero() && elapsed >= self.iteration_timeout {
+            bail!(
+                "Iteration timed out after {elapsed:.1?} (limit {:?}) before {step}",
+                self.iteration_timeout
+            );
+        }
+        Ok(())
+    }
+
+    fn iteration(&mut self) -> Result<()> {
+        log::debug!("[{}] Iteration", self.name);
+        self.iteration_start = Instant::now();
+        self.ensure_login_validity()?;
+        self.check_time_budget("fetching the timetable")?;
+        let untis_client = self.untis_client.as_ref().context("Not logged in")?;
+        let timetable = Timetable {
+            kind: self.resource_type,
+            id: self.timetable_id.context("Timetable ID not resolved")?,
+        };
+        let timezone: Tz = self.timezone.unwrap_or(Tz::UTC);
+
+        let now: DateTime<Utc> = Utc::now();
+        let (start, end) = self.range.dates(now.with_timezone(&timezone));
+        let days: Result<Vec<Day>> = untis_client.fetch_entries(start, end, timetable);
+        check_format_version(untis_client, &*self.notifier);
+        let days: Vec<Day> = days?;
+        self.check_time_budget("diffing the timetable")?;
+
+        if !self.clock_checked {
+            self.clock_checked = true;
+            check_clock_skew(untis_client, &*self.notifier, &self.name);
+        }
+
+        // Forget days that are no longer watched.
+        let old_len = self.days.len();
+        self.days.retain(|date, _| (start..=end).contains(date));
+        if self.days.len() < old_len {
+            log::info!("[{}] Another day, another victory for the OGs.", self.name);
+        }
+
+        let mut all_lessons = Vec::with_capacity(days.len());
+        for day in &days {
+            if self.no_school_statuses.contains(&day.status) {
+                log::debug!(
+                    "[{}] Skipping {} (status {}, no school).",
+                    self.name,
+                    day.date,
+                    day.status
+                );
+                // Start over with a fresh baseline once the day is a school day again.
+                self.days.remove(&day.date);
+                continue;
+            }
+            if !self.notify_weekdays.is_empty()
+                && !self.notify_weekdays.contains(&day.date.weekday())
+            {
+                log::debug!(
+                    "[{}] Skipping {} (not a notified weekday).",
+                    self.name,
+                    day.date
+                );
+                continue;
+            }
+            let mut lessons: Vec<LessonInfo> = if self.lenient_extract {
+                extract_all_lessons_lenient(day)
+            } else {
+                extract_all_lessons(day)?
+            };
+            if let Some(time_grid) = &self.time_grid {
+                time_grid.assign_periods(&mut lessons);
+            }
+            if self.deep_link {
+                let url = untis_client.timetable_web_url(timetable, day.date)?;
+                for lesson in &mut lessons {
+                    lesson.web_url = Some(url.clone());
+                }
+            }
+            all_lessons.push((day.date, lessons));
+        }
+        drop(days);
+
+        if let Some(state_store) = &self.state_store {
+            let days: SavedDays = all_lessons.iter().cloned().collect();
+            if let Err(e) = state_store.save(&self.name, days) {
+                log::warn!("[{}] Could not save the lessons: {e:?}", self.name);
+            }
+        }
+
+        if let Some(live_message) = &mut self.live_message {
+            let date: NaiveDate = get_relevant_date(now.with_timezone(&timezone));
+            if let Some((_, lessons)) = all_lessons.iter().find(|(d, _)| *d == date)
+                && let Err(e) = live_message.update(date, lessons)
+            {
+                log::warn!("[{}] Could not update the live message: {e:?}", self.name);
+            }
+        }
+
+        let paused: bool = self.pause_file.as_ref().is_some_and(|path| path.exists());
+        if paused != self.paused {
+            self.paused = paused;
+            if paused {
+                log::info!("[{}] Notifications paused.", self.name);
+            } else {
+                log::info!("[{}] Notifications resumed.", self.name);
+            }
+        }
+
+        for (date, lessons) in all_lessons {
+            if paused {
+                self.skip_day(date, lessons);
+            } else {
+                self.check_time_budget(&format!("notifying about {date}"))?;
+                self.diff_day(date, lessons)?;
+            }
+        }
+        if self.notify_exams {
+            self.check_time_budget("checking exams")?;
+            self.check_exams(start, timetable.id)?;
+        }
+        if self.notify_messages {
+            self.check_time_budget("checking messages of the day")?;
+            self.check_messages(now.with_timezone(&timezone).date_naive())?;
+        }
+        if let Some(days) = self.publish_lookahead_days {
+            self.check_time_budget("checking for newly published days")?;
+            self.check_published(start, days, timetable)?;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Fetch the upcoming exams (at most every [`EXAM_CHECK_INTERVAL`]) and report any changes
+    /// since the last check.
+    fn check_exams(&mut self, start: NaiveDate, class_id: i32) -> Result<()> {
+        if self
+            .exams_checked_at
+            .is_some_and(|at| at.elapsed() < EXAM_CHECK_INTERVAL)
+        {
+            return Ok(());
+        }
+        let untis_client = self.untis_client.as_ref().context("Not logged in")?;
+        let end: NaiveDate = start
+            .checked_add_days(Days::new(EXAM_LOOKAHEAD_DAYS))
+            .unwrap_or(start);
+        let exams: Vec<Exam> = untis_client.fetch_exams(start, end, class_id)?;
+        self.exams_checked_at = Some(Instant::now());
+
+        if let Some((old_end, old)) = &mut self.exams {
+            // Exams dropping out of the window at its start are over, not removed, and exams
+            // coming into view at its end are not new.
+            old.retain(|x| x.date >= start);
+            let visible: Vec<Exam> = exams
+                .iter()
+                .filter(|x| x.date <= *old_end)
+                .cloned()
+                .collect();
+            for change in diff_exams(old, &visible) {
+                if !self.paused {
+                    self.notifier.send_exam_change(&change)?;
+                }
+                // Remember each change once sent, so a failed send doesn't repeat the others.
+                change.apply(old);
+            }
+        }
+        self.exams = Some((end, exams));
+        Ok(())
+    }
+
+    /// Fetch the day's messages (at most every [`MESSAGE_CHECK_INTERVAL`]) and report those not
+    /// seen before. The messages present at the first check are taken as already known.
+    fn check_messages(&mut self, today: NaiveDate) -> Result<()> {
+        if self
+            .messages_checked_at
+            .is_some_and(|at| at.elapsed() < MESSAGE_CHECK_INTERVAL)
+        {
+            return Ok(());
+        }
+        let untis_client = self.untis_client.as_ref().context("Not logged in")?;
+        let messages: Vec<DayMessage> = untis_client.fetch_messages(today)?;
+        self.messages_checked_at = Some(Instant::now());
+
+        let Some(seen) = &mut self.seen_messages else {
+            self.seen_messages = Some(messages.iter().map(|x| x.id).collect());
+            return Ok(());
+        };
+        for message in &messages {
+            if seen.contains(&message.id) {
+                continue;
+            }
+            // While paused, messages are only marked as seen.
+            if !self.paused {
+                log::info!("[{}] New message of the day {}.", self.name, message.id);
+                self.notifier.send_day_message(message)?;
+            }
+            seen.insert(message.id);
+        }
+        Ok(())
+    }
+
+    /// Fetch the look-ahead range (at most every [`PUBLISH_CHECK_INTERVAL`]) and report days
+    /// that were empty at the last check but have lessons now.
+    fn check_published(&mut self, start: NaiveDate, days: u64, timetable: Timetable) -> Result<()> {
+        if self
+            .published_checked_at
+            .is_some_and(|at| at.elapsed() < PUBLISH_CHECK_INTERVAL)
+        {
+            return Ok(());
+        }
+        let untis_client = self.untis_client.as_ref().context("Not logged in")?;
+        let end: NaiveDate = start.checked_add_days(Days::new(days)).unwrap_or(start);
+        let entries = untis_client.fetch_entries_chunked(start, end, timetable);
+        self.published_checked_at = Some(Instant::now());
+
+        let mut empty_days: BTreeSet<NaiveDate> = BTreeSet::new();
+        let mut published: Vec<NaiveDate> = Vec::new();
+        for day in &entries.days {
+            if day.grid_entries.is_empty() {
+                empty_days.insert(day.date);
+            } else if self
+                .empty_days
+                .as_ref()
+                .is_some_and(|old| old.contains(&day.date))
+            {
+                published.push(day.date);
+            }
+        }
+        // Days that could not be fetched count as still empty, so they aren't missed.
+        if let Some(old) = &self.empty_days {
+            empty_days.extend(old.iter().filter(|date| {
+                entries
+                    .failed
+                    .iter()
+                    .any(|(from, to)| (from..=to).contains(date))
+            }));
+        }
+        self.empty_days = Some(empty_days);
+
+        if !published.is_empty() && !self.paused {
+            log::info!(
+                "[{}] Timetable published for {} days.",
+                self.name,
+                published.len()
+            );
+            self.notifier.send_published(&published)?;
+        }
+        Ok(())
+    }
+
+    /// Changes to lessons that are already over are not sent if this is set.
+    fn skip_before(&self) -> Option<NaiveDateTime> {
+        self.skip_past_lessons.then(|| {
+            let timezone: Tz = self.timezone.unwrap_or(Tz::UTC);
+            Utc::now().with_timezone(&timezone).naive_local()
+        })
+    }
+
+    /// Report the first timetable fetched for a day after starting, as chosen by `--on-startup`.
+    fn on_first_fetch(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<()> {
+        match self.on_startup {
+            StartupMode::Silent => {}
+            StartupMode::Announce => self.notifier.send_timetable(date, lessons)?,
+            StartupMode::Diff => {
+                if let Some(saved) = self.saved_days.get(&date) {
+                    let changes = diff_lessons(saved, lessons, self.teacher_match);
+                    send_potential_diffs(
+                        &*self.notifier,
+                        merge_changes(changes, self.merge_double_periods),
+                        &self.assignment_keywords,
+                        self.skip_before(),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the day's lessons as the new baseline without reporting anything, so resuming
+    /// after a pause doesn't send what changed in the meantime.
+    fn skip_day(&mut self, date: NaiveDate, lessons: Vec<LessonInfo>) {
+        let state: &mut DayState = self.days.entry(date).or_default();
+        state.prev_lessons = Some(lessons);
+        state.reported_lessons = None;
+        state.pending_since = None;
+        state.delivered.clear();
+    }
+
+    /// The reference lessons of the date's weekday (if there is a reference), moved to the date.
+    fn reference_lessons(&self, date: NaiveDate) -> Option<Vec<LessonInfo>> {
+        let reference = self.reference.as_ref()?;
+        let lessons = reference
+            .iter()
+            .filter(|lesson| lesson.datetime.weekday() == date.weekday())
+            .map(|lesson| LessonInfo {
+                datetime: date.and_time(lesson.datetime.time()),
+                end: date.and_time(lesson.end.time()),
+                ..lesson.clone()
+            })
+            .collect();
+        Some(lessons)
+    }
+
+    /// Compare a day's lessons against what was previously seen for that day.
+    fn diff_day(&mut self, date: NaiveDate, lessons: Vec<LessonInfo>) -> Result<()> {
+        let first_fetch: bool = self
+            .days
+            .get(&date)
+            .is_none_or(|state| state.prev_lessons.is_none());
+        if first_fetch && !self.started {
+            self.on_first_fetch(date, &lessons)?;
+        }
+        let skip_before: Option<NaiveDateTime> = self.skip_before();
+        let reference: Option<Vec<LessonInfo>> = self.reference_lessons(date);
+        // Deviations from the reference stay reported until they are undone.
+        let baseline = if reference.is_some() {
+            Baseline::Sticky
+        } else {
+            self.baseline
+        };
+
+        let state: &mut DayState = self.days.entry(date).or_default();
+        if state.prev_lessons.is_none()
+            && let Some(reference) = reference
+        {
+            state.prev_lessons = Some(reference);
+        }
+        let Some(prev_lessons) = &state.prev_lessons else {
+            state.prev_lessons = Some(lessons);
+            return Ok(());
+        };
+
+        // In digest mode, the first timetable seen stays the baseline until the digest is due.
+        if let Some(digest_at) = self.digest_at {
+            let timezone: Tz = self.timezone.unwrap_or(Tz::UTC);
+            let now: NaiveDateTime = Utc::now().with_timezone(&timezone).naive_local();
+            if state.digest_sent || now < date.and_time(digest_at) {
+                return Ok(());
+            }
+            let changes = diff_lessons(prev_lessons, &lessons, self.teacher_match);
+            let changes: Vec<LessonChange> = merge_changes(changes, self.merge_double_periods)
+                .into_iter()
+                .map(|x| self.assignment_keywords.highlight(x))
+                .collect();
+            log::info!(
+                "[{}] Sending digest of {} changes for {date}.",
+                self.name,
+                changes.len()
+            );
+            self.notifier.send_digest(date, &changes)?;
+            state.digest_sent = true;
+            return Ok(());
+        }
+
+        // In sticky mode, only changes since the last notification are new.
+        let reported: &[LessonInfo] = state.reported_lessons.as_deref().unwrap_or(prev_lessons);
+
+        // Hold back changes until the coalescing window closes, then send the net change.
+        if !self.coalesce_window.is_zero() && state.pending_since.is_none() && reported != lessons {
+            log::info!(
+                "[{}] Change detected; waiting {:?} for the timetable to settle.",
+                self.name,
+                self.coalesce_window
+            );
+            state.pending_since = Some(Instant::now());
+        }
+        let settled: bool = state
+            .pending_since
+            .is_none_or(|since| since.elapsed() >= self.coalesce_window);
+
+        if !settled {
+            return Ok(());
+        }
+        state.pending_since = None;
+
+        match baseline {
+            Baseline::Rolling => {
+                if *prev_lessons == lessons {
+                    return Ok(());
+                }
+                // If sending fails partway, the baseline is kept so the next iteration retries
+                // the rest of the same diff.
+                let changes = diff_lessons(prev_lessons, &lessons, self.teacher_match);
+                send_diffs_resumable(
+                    &*self.notifier,
+                    merge_changes(changes, self.merge_double_periods),
+                    &self.assignment_keywords,
+                    skip_before,
+                    &mut state.delivered,
+                )?;
+                state.delivered.clear();
+
+                // As there was a change, invalidate the "previous day".
+                state.prev_lessons = None;
+            }
+            Baseline::Sticky => {
+                if reported == lessons {
+                    return Ok(());
+                }
+                let changes =
+                    diff_lessons_since(prev_lessons, reported, &lessons, self.teacher_match);
+                for change in merge_changes(changes, self.merge_double_periods) {
+                    let change = self.assignment_keywords.highlight(change);
+                    if skip_before.is_some_and(|now| change.is_over(now))
+                        || state.delivered.contains(&change)
+                    {
+                        continue;
+                    }
+                    self.notifier.send_change(&change)?;
+                    state.delivered.push(change);
+                }
+                state.delivered.clear();
+                state.reported_lessons = Some(lessons);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The changes with those to double periods merged, if wanted (see [`merge_double_periods`]).
+fn merge_changes(changes: Vec<LessonChange>, merge: bool) -> Vec<LessonChange> {
+    if merge {
+        merge_double_periods(changes)
+    } else {
+        changes
+    }
+}
+
+/// Load a week of reference lessons, as written by `export-reference`.
+fn load_reference(path: &Path) -> Result<Vec<LessonInfo>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Could not read reference file {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("Invalid reference file {}", path.display()))
+}
+
+fn detect_timezone(untis_client: &UntisClient) -> Tz {
+    match untis_client.fetch_school_timezone() {
+        Ok(tz) => {
+            log::info!("Detected school timezone {tz}.");
+            tz
+        }
+        Err(e) => {
+            log::warn!("Could not detect school timezone; falling back to UTC: {e:?}");
+            Tz::UTC
+        }
+    }
+}
+
+fn fetch_time_grid(untis_client: &UntisClient) -> Option<TimeGrid> {
+    match untis_client.fetch_time_grid() {
+        Ok(time_grid) => Some(time_grid),
+        Err(e) => {
+            log::warn!("Could not fetch the bell schedule; showing times only: {e:?}");
+            None
+        }
+    }
+}
+
+/// Warn (once per process) if the API format changed, since parsing might be degraded.
+fn check_format_version(untis_client: &UntisClient, notifier: &dyn Notifier) {
+    let Some(version) = untis_client.format_version() else {
+        return;
+    };
+    let expected = expected_format_version();
+    if version != expected && !FORMAT_WARNING_SENT.swap(true, Ordering::Relaxed) {
+        notifier.send_warning(&format!(
+            "WebUntis API format changed from {expected} to {version}; \
+             notifications may be degraded — please update."
+        ));
+    }
+}
+
+fn check_clock_skew(untis_client: &UntisClient, notifier: &dyn Notifier, name: &str) {
+    let Some(skew) = untis_client.clock_skew() else {
+        log::debug!("[{name}] Untis server did not send its time; skipping clock check.");
+        return;
+    };
+    if skew.abs() > MAX_CLOCK_SKEW {
+        let direction = if skew < TimeDelta::zero() {
+            "ahead of"
+        } else {
+            "behind"
+        };
+        notifier.send_warning(&format!(
+            "[{name}] The local clock is {} seconds {direction} the Untis server; \
+             the bot might be watching the wrong day.",
+            skew.num_seconds().abs(),
+        ));
+    }
+}
+
+fn get_relevant_date(now: DateTime<Tz>) -> NaiveDate {
+    let mut date: NaiveDate = now.date_naive();
+
+    if now.hour() >= 18 {
+        // After 18:00, show changes for tomorrow instead of today.
+        date = date.checked_add_days(Days::new(1)).unwrap();
+    }
+
+    date
+}
+
+fn get_sleep_time(now: DateTime<Utc>, off_hours: Duration) -> Duration {
+    let time: NaiveTime = now.time();
+    let secs = match time.hour() {
+        7..8 => 4,
+        6..11 => 20,
+        11..16 => 40,
+        _ => return off_hours,
+    };
+    Duration::from_secs(secs)
+}
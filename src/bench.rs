@@ -0,0 +1,63 @@
+use std::{
+    fs, hint,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use webuntis::{
+    LessonInfo, TeacherMatch, diff_lessons, extract_all_lessons_lenient,
+    untis::entries::{Day, parse_entries},
+};
+
+/// The stages of a watch iteration, minus the network and notifications.
+const STAGES: [&str; 4] = ["read", "parse", "extract", "diff"];
+
+/// Time every stage over and over on a saved `timetable/entries` response and print a table of
+/// min/median/max timings.
+///
+/// Reading the file stands in for fetching; every day is diffed against the next one, so the
+/// diff has something to find.
+pub fn run(path: &Path, iterations: u64) -> Result<()> {
+    let mut timings: [Vec<Duration>; STAGES.len()] = Default::default();
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        timings[0].push(start.elapsed());
+
+        let start = Instant::now();
+        let days: Vec<Day> = parse_entries(&text)?;
+        timings[1].push(start.elapsed());
+
+        let start = Instant::now();
+        let lessons: Vec<Vec<LessonInfo>> = days.iter().map(extract_all_lessons_lenient).collect();
+        timings[2].push(start.elapsed());
+
+        let start = Instant::now();
+        for pair in lessons.windows(2) {
+            hint::black_box(diff_lessons(&pair[0], &pair[1], TeacherMatch::LongName));
+        }
+        timings[3].push(start.elapsed());
+    }
+
+    println!(
+        "{:<8} {:>12} {:>12} {:>12}",
+        "stage", "min", "median", "max"
+    );
+    for (stage, durations) in STAGES.iter().zip(&mut timings) {
+        durations.sort_unstable();
+        let (Some(min), Some(max)) = (durations.first(), durations.last()) else {
+            continue;
+        };
+        let median = durations[durations.len() / 2];
+        println!(
+            "{stage:<8} {:>12} {:>12} {:>12}",
+            format!("{min:.2?}"),
+            format!("{median:.2?}"),
+            format!("{max:.2?}"),
+        );
+    }
+    Ok(())
+}
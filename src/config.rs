@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, bail};
+use chrono_tz::Tz;
+use reqwest::Url;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A single school account/timetable to watch, together with where to send its notifications.
+///
+/// Serializes with the password, webhook tokens and header values redacted.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    pub school: String,
+    pub username: String,
+    #[serde(serialize_with = "serialize_redacted")]
+    pub password: String,
+    /// Regional server host, instead of the school subdomain.
+    #[serde(default)]
+    pub server: Option<String>,
+    pub timetable_id: Option<i32>,
+    pub class: Option<String>,
+
+    /// Where to send notifications to; printed to stdout if neither webhook is given.
+    #[serde(
+        default,
+        deserialize_with = "parse_opt_from_str",
+        serialize_with = "serialize_opt_redacted_url"
+    )]
+    pub discord_webhook_url: Option<Url>,
+
+    /// Post to this Discord channel as a bot instead of through a webhook.
+    #[serde(default, serialize_with = "serialize_opt_redacted")]
+    pub discord_bot_token: Option<String>,
+    #[serde(default)]
+    pub discord_channel_id: Option<u64>,
+
+    /// A generic webhook receiving notifications as JSON, instead of Discord.
+    #[serde(
+        default,
+        deserialize_with = "parse_opt_from_str",
+        serialize_with = "serialize_opt_redacted_url"
+    )]
+    pub webhook_url: Option<Url>,
+
+    /// Extra headers sent to the generic webhook, e.g. `Authorization`.
+    #[serde(default, serialize_with = "serialize_redacted_values")]
+    pub webhook_headers: HashMap<String, String>,
+
+    #[serde(
+        default,
+        deserialize_with = "parse_opt_from_str",
+        serialize_with = "serialize_opt_display"
+    )]
+    pub timezone: Option<Tz>,
+
+    /// A week of lessons (written by `export-reference`) to report deviations from, instead of
+    /// changes between fetches.
+    #[serde(default)]
+    pub reference_file: Option<PathBuf>,
+}
+
+/// Load a list of watches from a JSON file.
+pub fn load_watches(path: &Path) -> Result<Vec<WatchConfig>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Could not read config file {}", path.display()))?;
+    let watches: Vec<WatchConfig> = serde_json::from_str(&text)
+        .with_context(|| format!("Could not parse config file {}", path.display()))?;
+    for watch in &watches {
+        watch.validate()?;
+    }
+    Ok(watches)
+}
+
+impl WatchConfig {
+    fn validate(&self) -> Result<()> {
+        let bot = self.discord_bot_token.is_some();
+        let targets = [
+            self.discord_webhook_url.is_some(),
+            bot,
+            self.webhook_url.is_some(),
+        ];
+        if targets.into_iter().filter(|&x| x).count() > 1 {
+            bail!(
+                "Watch for {}@{} can only have one of `discord_webhook_url`, `discord_bot_token` \
+                 and `webhook_url`",
+                self.username,
+                self.school,
+            );
+        }
+        if bot != self.discord_channel_id.is_some() {
+            bail!(
+                "Watch for {}@{} needs both `discord_bot_token` and `discord_channel_id`",
+                self.username,
+                self.school,
+            );
+        }
+        match (self.timetable_id, &self.class) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => bail!(
+                "Watch for {}@{} needs exactly one of `timetable_id` and `class`",
+                self.username,
+                self.school,
+            ),
+        }
+    }
+}
+
+/// Shown in place of secrets when printing the configuration.
+pub const REDACTED: &str = "<redacted>";
+
+/// Keep only the URL's origin, since the path or query may contain a token.
+pub fn redact_url(url: &Url) -> String {
+    format!("{}/{REDACTED}", url.origin().ascii_serialization())
+}
+
+/// Serializes the URL redacted (see [`redact_url`]).
+pub fn serialize_opt_redacted_url<S: Serializer>(
+    url: &Option<Url>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    url.as_ref().map(redact_url).serialize(serializer)
+}
+
+fn serialize_redacted<S: Serializer>(_: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+fn serialize_opt_redacted<S: Serializer>(
+    secret: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    secret.as_ref().map(|_| REDACTED).serialize(serializer)
+}
+
+/// Keeps the keys, e.g. the names of headers.
+fn serialize_redacted_values<S: Serializer>(
+    map: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.keys().map(|key| (key, REDACTED)))
+}
+
+fn serialize_opt_display<S: Serializer, T: Display>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn parse_opt_from_str<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Option::<String>::deserialize(d)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
@@ -0,0 +1,153 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Result, bail};
+use chrono::{
+    NaiveDateTime,
+    format::{Item, StrftimeItems},
+};
+use serde::{Serialize, Serializer};
+
+/// How lesson dates are shown to humans in notifications, as a `chrono` strftime pattern.
+///
+/// Lesson times from Untis are already in the school's local time, so they are shown as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+    pattern: String,
+}
+
+impl DateFormat {
+    /// Weekday first, since that is what students look for, e.g. `Fri 10 May, 08:00`.
+    pub const DEFAULT_PATTERN: &str = "%a %-d %b, %H:%M";
+
+    pub fn new(pattern: impl Into<String>) -> Result<Self> {
+        let pattern = pattern.into();
+        if StrftimeItems::new(&pattern).any(|item| item == Item::Error) {
+            bail!("Invalid date format {pattern:?}; see chrono's strftime documentation");
+        }
+        Ok(Self { pattern })
+    }
+
+    #[must_use]
+    pub fn format(&self, datetime: NaiveDateTime) -> String {
+        datetime.format(&self.pattern).to_string()
+    }
+
+    /// Only the date, using the date part of the pattern (e.g. `%a %-d %b` of the default), or
+    /// `%Y-%m-%d` if the pattern has none.
+    #[must_use]
+    pub fn format_date(&self, datetime: NaiveDateTime) -> String {
+        let pattern = self.part(false).unwrap_or("%Y-%m-%d");
+        datetime.format(pattern).to_string()
+    }
+
+    /// Only the time, using the time part of the pattern (e.g. `%H:%M` of the default), or
+    /// `%H:%M` if the pattern has none.
+    #[must_use]
+    pub fn format_time(&self, datetime: NaiveDateTime) -> String {
+        let pattern = self.part(true).unwrap_or("%H:%M");
+        datetime.format(pattern).to_string()
+    }
+
+    /// The pattern from its first to its last time (or date) specifier, including whatever is
+    /// between them.
+    fn part(&self, time: bool) -> Option<&str> {
+        let mut span: Option<(usize, usize)> = None;
+        let mut chars = self.pattern.char_indices();
+        while let Some((start, c)) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            // Skip padding flags and modifiers like `%-d`, `%:z` or `%.3f`.
+            let Some((end, conversion)) = chars
+                .by_ref()
+                .find(|(_, x)| !matches!(x, '-' | '_' | '0'..='9' | ':' | '.'))
+            else {
+                break;
+            };
+            if matches!(conversion, '%' | 'n' | 't') {
+                continue;
+            }
+            if is_time_specifier(conversion) == time {
+                let end = end + conversion.len_utf8();
+                span = Some(span.map_or((start, end), |(first, _)| (first, end)));
+            }
+        }
+        span.map(|(start, end)| &self.pattern[start..end])
+    }
+}
+
+/// Whether the strftime conversion (the letter after `%`) formats part of the time of day.
+const fn is_time_specifier(conversion: char) -> bool {
+    matches!(
+        conversion,
+        'H' | 'k' | 'I' | 'l' | 'M' | 'S' | 'f' | 'p' | 'P' | 'r' | 'R' | 'T' | 'X'
+    )
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        Self {
+            pattern: Self::DEFAULT_PATTERN.to_string(),
+        }
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+/// Serializes as the pattern.
+impl Serialize for DateFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn datetime() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 10)
+            .unwrap()
+            .and_hms_opt(8, 5, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn splits_the_default_pattern() {
+        let format = DateFormat::default();
+        assert_eq!(format.format(datetime()), "Fri 10 May, 08:05");
+        assert_eq!(format.format_date(datetime()), "Fri 10 May");
+        assert_eq!(format.format_time(datetime()), "08:05");
+    }
+
+    #[test]
+    fn splits_patterns_with_time_first() {
+        let format = DateFormat::new("%-I:%M %p on %d.%m.%Y").unwrap();
+        assert_eq!(format.format_date(datetime()), "10.05.2024");
+        assert_eq!(format.format_time(datetime()), "8:05 AM");
+    }
+
+    #[test]
+    fn falls_back_to_iso_parts() {
+        let format = DateFormat::new("%A").unwrap();
+        assert_eq!(format.format_date(datetime()), "Friday");
+        assert_eq!(format.format_time(datetime()), "08:05");
+        let format = DateFormat::new("%H.%M Uhr").unwrap();
+        assert_eq!(format.format_date(datetime()), "2024-05-10");
+        assert_eq!(format.format_time(datetime()), "08.05");
+    }
+}
@@ -1,61 +1,1003 @@
-use anyhow::Result;
+use std::{collections::BTreeMap, str::FromStr};
 
-use crate::{LessonInfo, discord::DiscordClient, untis::entries::Status};
+use anyhow::{Result, bail};
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::Serialize;
 
-pub fn send_potential_diffs(
-    discord: &DiscordClient,
+use crate::{
+    Cause, Error, LessonInfo,
+    notify::Notifier,
+    or_absent,
+    untis::entries::{EntryText, EntryTextType, EntryType, Status},
+};
+
+/// A single notification-worthy difference between two versions of a lesson.
+///
+/// Every variant carries the new state of the lesson (or the old one, if it was removed), plus
+/// whatever old values are needed to describe the change.
+///
+/// Serializes with the [`Self::kind`] in a `kind` field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind")]
+#[non_exhaustive]
+pub enum LessonChange {
+    #[serde(rename = "cancellation")]
+    Cancelled {
+        lesson: LessonInfo,
+        old_status: Status,
+    },
+    #[serde(rename = "change")]
+    Changed {
+        lesson: LessonInfo,
+        old_status: Status,
+    },
+    /// The entry became an exam (or event), or stopped being one.
+    #[serde(rename = "entry_type")]
+    EntryType {
+        lesson: LessonInfo,
+        old_entry_type: EntryType,
+    },
+    #[serde(rename = "subject")]
+    Subject {
+        lesson: LessonInfo,
+        old_subject: Option<String>,
+        old_status: Status,
+    },
+    #[serde(rename = "teacher")]
+    Teacher {
+        lesson: LessonInfo,
+        old_teacher: Option<String>,
+        old_status: Status,
+        /// The teacher reported in between, if the substitute was replaced again (only with a
+        /// fixed baseline, see [`diff_lessons_since`]).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        via_teacher: Option<String>,
+    },
+    #[serde(rename = "room")]
+    Room {
+        lesson: LessonInfo,
+        old_room: Option<String>,
+    },
+    /// The lesson starts or ends at another time. A lesson that moved is only recognized as
+    /// such if it kept its subject and still overlaps its old time; otherwise it is reported as
+    /// removed and another one as added.
+    #[serde(rename = "time")]
+    Time {
+        lesson: LessonInfo,
+        old_start: NaiveDateTime,
+        old_end: NaiveDateTime,
+    },
+    /// The substitution text appeared, changed or was removed; it usually explains a substitution.
+    #[serde(rename = "substitution")]
+    Substitution {
+        lesson: LessonInfo,
+        old_substitution_text: Option<String>,
+    },
+    #[serde(rename = "notes")]
+    Notes {
+        lesson: LessonInfo,
+        old_texts: Vec<EntryText>,
+    },
+    /// A new text of the lesson sets work, e.g. a worksheet to do during a substitution (see
+    /// [`AssignmentKeywords`]). Reported instead of the notes or substitution change.
+    #[serde(rename = "assignment")]
+    Assignment { lesson: LessonInfo, text: String },
+    /// The online meeting link found in the lesson's texts appeared, changed or was removed.
+    #[serde(rename = "meeting_link")]
+    MeetingLink {
+        lesson: LessonInfo,
+        old_meeting_url: Option<String>,
+    },
+    /// A cancelled (or removed) lesson takes place after all.
+    #[serde(rename = "reinstated")]
+    Reinstated {
+        lesson: LessonInfo,
+        old_status: Status,
+    },
+    /// A previously reported change was undone; the lesson is back to its original state.
+    #[serde(rename = "reverted")]
+    Reverted { lesson: LessonInfo },
+    #[serde(rename = "added")]
+    Added { lesson: LessonInfo },
+    #[serde(rename = "removed")]
+    Removed { lesson: LessonInfo },
+}
+
+impl LessonChange {
+    /// All possible values of [`Self::kind`].
+    pub const KINDS: [&str; 15] = [
+        "cancellation",
+        "change",
+        "reinstated",
+        "entry_type",
+        "subject",
+        "teacher",
+        "room",
+        "time",
+        "substitution",
+        "notes",
+        "assignment",
+        "meeting_link",
+        "reverted",
+        "added",
+        "removed",
+    ];
+
+    /// The (new) lesson this change refers to.
+    #[must_use]
+    pub const fn lesson(&self) -> &LessonInfo {
+        match self {
+            Self::Cancelled { lesson, .. }
+            | Self::Changed { lesson, .. }
+            | Self::Reinstated { lesson, .. }
+            | Self::EntryType { lesson, .. }
+            | Self::Subject { lesson, .. }
+            | Self::Teacher { lesson, .. }
+            | Self::Room { lesson, .. }
+            | Self::Time { lesson, .. }
+            | Self::Substitution { lesson, .. }
+            | Self::Notes { lesson, .. }
+            | Self::Assignment { lesson, .. }
+            | Self::MeetingLink { lesson, .. }
+            | Self::Reverted { lesson }
+            | Self::Added { lesson }
+            | Self::Removed { lesson } => lesson,
+        }
+    }
+
+    pub(crate) const fn lesson_mut(&mut self) -> &mut LessonInfo {
+        match self {
+            Self::Cancelled { lesson, .. }
+            | Self::Changed { lesson, .. }
+            | Self::Reinstated { lesson, .. }
+            | Self::EntryType { lesson, .. }
+            | Self::Subject { lesson, .. }
+            | Self::Teacher { lesson, .. }
+            | Self::Room { lesson, .. }
+            | Self::Time { lesson, .. }
+            | Self::Substitution { lesson, .. }
+            | Self::Notes { lesson, .. }
+            | Self::Assignment { lesson, .. }
+            | Self::MeetingLink { lesson, .. }
+            | Self::Reverted { lesson }
+            | Self::Added { lesson }
+            | Self::Removed { lesson } => lesson,
+        }
+    }
+
+    /// Whether the affected lesson already ended at `now` (in the school's local time).
+    #[must_use]
+    pub fn is_over(&self, now: NaiveDateTime) -> bool {
+        self.lesson().end < now
+    }
+
+    /// A short machine-friendly identifier of the type of change.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Cancelled { .. } => "cancellation",
+            Self::Changed { .. } => "change",
+            Self::Reinstated { .. } => "reinstated",
+            Self::EntryType { .. } => "entry_type",
+            Self::Subject { .. } => "subject",
+            Self::Teacher { .. } => "teacher",
+            Self::Room { .. } => "room",
+            Self::Time { .. } => "time",
+            Self::Substitution { .. } => "substitution",
+            Self::Notes { .. } => "notes",
+            Self::Assignment { .. } => "assignment",
+            Self::MeetingLink { .. } => "meeting_link",
+            Self::Reverted { .. } => "reverted",
+            Self::Added { .. } => "added",
+            Self::Removed { .. } => "removed",
+        }
+    }
+
+    #[must_use]
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Self::Cancelled { .. } => "Lesson Cancellation",
+            Self::Changed { .. } => "Lesson Change",
+            Self::Reinstated { .. } => "Lesson Reinstated",
+            Self::EntryType { .. } => "Lesson Type Changed",
+            Self::Subject { .. } => "Subject Changed",
+            Self::Teacher { .. } => "Teacher Changed",
+            Self::Room { .. } => "Room Changed",
+            Self::Time { .. } => "Time Changed",
+            Self::Substitution { .. } => "Substitution Info",
+            Self::Notes { .. } => "Notes Changed",
+            Self::Assignment { .. } => "Assignment Set",
+            Self::MeetingLink { .. } => "Meeting Link Changed",
+            Self::Reverted { .. } => "Change Reverted",
+            Self::Added { .. } => "Lesson Added",
+            Self::Removed { .. } => "Lesson Removed",
+        }
+    }
+
+    /// The old and new value of whatever changed, if the change can be expressed that way.
+    #[must_use]
+    pub fn before_after(&self) -> Option<(String, String)> {
+        match self {
+            Self::Cancelled { lesson, old_status }
+            | Self::Changed { lesson, old_status }
+            | Self::Reinstated { lesson, old_status } => {
+                Some((old_status.to_string(), lesson.status.to_string()))
+            }
+            Self::EntryType {
+                lesson,
+                old_entry_type,
+            } => Some((old_entry_type.to_string(), lesson.entry_type.to_string())),
+            Self::Subject {
+                lesson,
+                old_subject,
+                ..
+            } => Some(pair(old_subject, &lesson.subject)),
+            Self::Teacher {
+                lesson,
+                old_teacher,
+                ..
+            } => Some(pair(old_teacher, &lesson.teacher)),
+            Self::Room { lesson, old_room } => Some(pair(old_room, &lesson.room)),
+            Self::Time {
+                lesson, old_start, ..
+            } => Some((format_time(*old_start), format_time(lesson.datetime))),
+            Self::Substitution {
+                lesson,
+                old_substitution_text,
+            } => Some(pair(old_substitution_text, &lesson.substitution_text)),
+            Self::MeetingLink {
+                lesson,
+                old_meeting_url,
+            } => Some(pair(old_meeting_url, &lesson.meeting_url)),
+            Self::Notes { .. }
+            | Self::Assignment { .. }
+            | Self::Reverted { .. }
+            | Self::Added { .. }
+            | Self::Removed { .. } => None,
+        }
+    }
+
+    #[must_use]
+    pub fn description(&self) -> String {
+        match self {
+            Self::Cancelled { lesson, old_status } | Self::Changed { lesson, old_status } => {
+                format!(
+                    "Lesson Status changed from {} to {}.",
+                    old_status, lesson.status,
+                )
+            }
+            Self::Reinstated { old_status, .. } => format!(
+                "The lesson is back on! It was {} before.",
+                old_status.to_string().to_lowercase()
+            ),
+            Self::EntryType {
+                lesson,
+                old_entry_type,
+            } => format!(
+                "{} changed to {}.",
+                old_entry_type,
+                lesson.entry_type.to_string().to_lowercase()
+            ),
+            Self::Subject {
+                lesson,
+                old_subject,
+                old_status,
+            } => format!(
+                "Subject changed from {} ({}) to {} ({}).",
+                or_absent(old_subject.as_deref()),
+                old_status,
+                or_absent(lesson.subject.as_deref()),
+                lesson.subject_status
+            ),
+            Self::Teacher {
+                lesson,
+                old_teacher,
+                old_status,
+                via_teacher,
+            } => {
+                let mut description = format!(
+                    "Teacher changed from {} ({}) to {} ({})",
+                    or_absent(old_teacher.as_deref()),
+                    old_status,
+                    or_absent(lesson.teacher.as_deref()),
+                    lesson.teacher_status
+                );
+                if let Some(via) = via_teacher {
+                    description.push_str(&format!(" (was briefly {via})"));
+                }
+                description.push('.');
+                description
+            }
+            Self::Room { lesson, old_room } => format!(
+                "Room changed from {} to {} ({}).",
+                or_absent(old_room.as_deref()),
+                or_absent(lesson.room_label().as_deref()),
+                lesson.room_status
+            ),
+            Self::Time {
+                lesson,
+                old_start,
+                old_end,
+            } => format!(
+                "Lesson moved from {}–{} to {}–{}.",
+                format_time(*old_start),
+                format_time(*old_end),
+                format_time(lesson.datetime),
+                format_time(lesson.end),
+            ),
+            Self::Substitution { lesson, .. } => lesson.substitution_text.as_ref().map_or_else(
+                || "The substitution info was removed.".to_string(),
+                |text| format!("Substitution info: {text}"),
+            ),
+            Self::Notes { lesson, old_texts } => describe_texts(old_texts, &lesson.texts),
+            Self::Assignment { text, .. } => format!("New assignment: {text}"),
+            Self::MeetingLink {
+                lesson,
+                old_meeting_url,
+            } => match (old_meeting_url, &lesson.meeting_url) {
+                (_, Some(url)) => format!("Join the lesson online: {url}"),
+                (Some(_), None) => "The meeting link was removed.".to_string(),
+                (None, None) => "The meeting link changed.".to_string(),
+            },
+            Self::Reverted { .. } => {
+                "The previously reported changes were undone; the lesson is as originally planned."
+                    .to_string()
+            }
+            Self::Added { .. } => "A lesson was added to the timetable.".to_string(),
+            Self::Removed { .. } => "The lesson was removed from the timetable.".to_string(),
+        }
+    }
+
+    /// The change on a single line, for digests listing all of a day's changes at once.
+    #[must_use]
+    pub fn summary_line(&self) -> String {
+        let lesson = self.lesson();
+        format!(
+            "{} {}: {} - {}",
+            format_time(lesson.datetime),
+            or_absent(lesson.subject.as_deref()),
+            self.title(),
+            self.description().replace('\n', "; "),
+        )
+    }
+}
+
+/// Which of the teacher's names decides whether the teacher changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TeacherMatch {
+    /// The full name, as shown in notifications.
+    #[default]
+    LongName,
+    /// The abbreviation, for schools that keep it stable while tweaking the full name.
+    ShortName,
+}
+
+impl TeacherMatch {
+    fn teacher(self, lesson: &LessonInfo) -> Option<&str> {
+        match self {
+            Self::LongName => lesson.teacher.as_deref(),
+            Self::ShortName => lesson.teacher_short_name.as_deref(),
+        }
+    }
+}
+
+impl FromStr for TeacherMatch {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        Ok(match string.to_ascii_lowercase().as_str() {
+            "long-name" => Self::LongName,
+            "short-name" => Self::ShortName,
+            _ => bail!("Unknown teacher name {string:?}; expected long-name or short-name"),
+        })
+    }
+}
+
+/// Words that mark a lesson's text as setting work, like "Arbeitsblatt" in "Bitte das
+/// Arbeitsblatt 3 bearbeiten". Matched case-insensitively anywhere in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentKeywords {
+    keywords: Vec<String>,
+}
+
+impl AssignmentKeywords {
+    /// German and English words commonly used in substitution notes, limited to those that
+    /// hardly mean anything but setting work.
+    pub const DEFAULT: [&str; 5] = [
+        "arbeitsblatt",
+        "arbeitsauftrag",
+        "hausaufgabe",
+        "worksheet",
+        "homework",
+    ];
+
+    /// Detect these keywords instead of the default ones; none turns detection off.
+    #[must_use]
+    pub fn new(keywords: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let keywords = keywords
+            .into_iter()
+            .map(|x| x.as_ref().trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .collect();
+        Self { keywords }
+    }
+
+    #[must_use]
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.keywords.iter().any(|x| text.contains(x.as_str()))
+    }
+
+    /// The change as an [`LessonChange::Assignment`] if it is a notes or substitution change
+    /// whose new text mentions any of the keywords, otherwise unchanged.
+    #[must_use]
+    pub fn highlight(&self, change: LessonChange) -> LessonChange {
+        let text: Option<String> = match &change {
+            LessonChange::Substitution {
+                lesson,
+                old_substitution_text,
+            } => lesson
+                .substitution_text
+                .clone()
+                .filter(|x| Some(x) != old_substitution_text.as_ref()),
+            LessonChange::Notes { lesson, old_texts } => lesson
+                .texts
+                .iter()
+                .find(|x| !old_texts.contains(x) && self.matches(&x.text))
+                .map(|x| x.text.clone()),
+            _ => None,
+        };
+        match text {
+            Some(text) if self.matches(&text) => LessonChange::Assignment {
+                lesson: change.lesson().clone(),
+                text,
+            },
+            _ => change,
+        }
+    }
+}
+
+impl Default for AssignmentKeywords {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT)
+    }
+}
+
+/// Identifies a lesson within a day, so that two fetches of the same day can be matched up.
+///
+/// Lessons are keyed by their start time. Only when several lessons start at the same time
+/// (e.g. split classes) are they told apart by subject, then room, and finally by their order
+/// of appearance. Ties are broken the same way on every fetch, so the key is stable as long
+/// as the timetable itself doesn't change.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LessonKey {
+    pub start: NaiveTime,
+    /// Subject and room; only set if another lesson starts at the same time.
+    pub disambiguator: Option<(Option<String>, Option<String>)>,
+    /// Position among lessons with the same start time, subject and room.
+    pub index: usize,
+}
+
+/// Keys all lessons of a day (see [`LessonKey`]).
+#[must_use]
+pub fn key_lessons(lessons: &[LessonInfo]) -> BTreeMap<LessonKey, &LessonInfo> {
+    let mut by_start: BTreeMap<NaiveTime, Vec<&LessonInfo>> = BTreeMap::new();
+    for lesson in lessons {
+        by_start
+            .entry(lesson.datetime.time())
+            .or_default()
+            .push(lesson);
+    }
+
+    let mut keyed = BTreeMap::new();
+    for (start, mut group) in by_start {
+        if let [lesson] = group[..] {
+            let key = LessonKey {
+                start,
+                disambiguator: None,
+                index: 0,
+            };
+            keyed.insert(key, lesson);
+            continue;
+        }
+        // Stable sort, so identical lessons keep their order of appearance.
+        group.sort_by(|a, b| (&a.subject, &a.room).cmp(&(&b.subject, &b.room)));
+        let mut index: usize = 0;
+        for (i, lesson) in group.iter().enumerate() {
+            let same_as_previous = i > 0
+                && (&group[i - 1].subject, &group[i - 1].room) == (&lesson.subject, &lesson.room);
+            index = if same_as_previous { index + 1 } else { 0 };
+            let key = LessonKey {
+                start,
+                disambiguator: Some((lesson.subject.clone(), lesson.room.clone())),
+                index,
+            };
+            keyed.insert(key, *lesson);
+        }
+    }
+    keyed
+}
+
+/// Compares two versions of the same day's lessons and collects all changes.
+///
+/// Lessons are matched by their [`LessonKey`]; lessons only present in one version are
+/// reported as added or removed, unless they were moved (see [`LessonChange::Time`]). On exam
+/// days, lessons in periods left empty are not reported as removed.
+#[must_use]
+pub fn diff_lessons(
+    old: &[LessonInfo],
+    new_day: &[LessonInfo],
+    teacher_match: TeacherMatch,
+) -> Vec<LessonChange> {
+    let old = key_lessons(old);
+    let new = key_lessons(new_day);
+    let mut changes = Vec::new();
+    for (key, old_lesson) in &old {
+        match new.get(key) {
+            Some(new_lesson) => diff_lesson(old_lesson, new_lesson, teacher_match, &mut changes),
+            None if is_unpublished(old_lesson, new_day) => {}
+            None => changes.push(LessonChange::Removed {
+                lesson: (*old_lesson).clone(),
+            }),
+        }
+    }
+    changes.extend(
+        new.iter()
+            .filter(|(key, _)| !old.contains_key(key))
+            .map(|(_, lesson)| LessonChange::Added {
+                lesson: (*lesson).clone(),
+            }),
+    );
+    pair_moved_lessons(changes, teacher_match)
+}
+
+/// Compares the latest lessons against a fixed `baseline` instead of the previous version.
+///
+/// Only lessons that differ from what was last reported (`reported_day`) produce changes: the
+/// net change since the baseline, or [`LessonChange::Reverted`] if the lesson is back to its
+/// baseline.
+#[must_use]
+pub fn diff_lessons_since(
+    baseline: &[LessonInfo],
+    reported_day: &[LessonInfo],
+    new_day: &[LessonInfo],
+    teacher_match: TeacherMatch,
+) -> Vec<LessonChange> {
+    let baseline = key_lessons(baseline);
+    let reported = key_lessons(reported_day);
+    let new = key_lessons(new_day);
+    let mut changes = Vec::new();
+    for (key, new_lesson) in &new {
+        if reported.get(key) == Some(new_lesson) {
+            continue;
+        }
+        match baseline.get(key) {
+            // Back from an exam day, where nothing was published for the lesson's period.
+            Some(base_lesson)
+                if base_lesson == new_lesson
+                    && !reported.contains_key(key)
+                    && is_unpublished(new_lesson, reported_day) => {}
+            Some(base_lesson) if base_lesson == new_lesson => {
+                // An undone cancellation deserves more than a plain "reverted".
+                let change = match reported.get(key) {
+                    Some(old) if is_reinstatement(old.status, new_lesson.status) => {
+                        LessonChange::Reinstated {
+                            lesson: (*new_lesson).clone(),
+                            old_status: old.status,
+                        }
+                    }
+                    _ => LessonChange::Reverted {
+                        lesson: (*new_lesson).clone(),
+                    },
+                };
+                changes.push(change);
+            }
+            Some(base_lesson) => {
+                let first = changes.len();
+                diff_lesson(base_lesson, new_lesson, teacher_match, &mut changes);
+                if let Some(reported_lesson) = reported.get(key) {
+                    note_teacher_chain(
+                        &mut changes[first..],
+                        base_lesson,
+                        reported_lesson,
+                        teacher_match,
+                    );
+                }
+            }
+            None => changes.push(LessonChange::Added {
+                lesson: (*new_lesson).clone(),
+            }),
+        }
+    }
+    changes.extend(
+        reported
+            .iter()
+            .filter(|(key, lesson)| !new.contains_key(key) && !is_unpublished(lesson, new_day))
+            .map(|(_, lesson)| LessonChange::Removed {
+                lesson: (*lesson).clone(),
+            }),
+    );
+    pair_moved_lessons(changes, teacher_match)
+}
+
+/// Replaces a removed and an added lesson of the same subject whose times overlap with the
+/// changes between them, since the lesson was moved rather than replaced.
+fn pair_moved_lessons(
+    changes: Vec<LessonChange>,
+    teacher_match: TeacherMatch,
+) -> Vec<LessonChange> {
+    let moved = |old: &LessonInfo, new: &LessonInfo| {
+        let overlaps = new.datetime.time() < old.end.time() && old.datetime.time() < new.end.time();
+        overlaps && old.subject == new.subject
+    };
+    // Index of each removed lesson to the index of the added lesson it moved to.
+    let mut moves: BTreeMap<usize, usize> = BTreeMap::new();
+    for (i, change) in changes.iter().enumerate() {
+        let LessonChange::Removed { lesson: old } = change else {
+            continue;
+        };
+        let moved_to = (0..changes.len()).find(|&j| {
+            matches!(&changes[j], LessonChange::Added { lesson: new } if moved(old, new))
+                && !moves.values().any(|&taken| taken == j)
+        });
+        if let Some(j) = moved_to {
+            moves.insert(i, j);
+        }
+    }
+    if moves.is_empty() {
+        return changes;
+    }
+
+    let mut paired = Vec::with_capacity(changes.len());
+    for (i, change) in changes.iter().enumerate() {
+        if let Some(&j) = moves.get(&i) {
+            diff_lesson(
+                change.lesson(),
+                changes[j].lesson(),
+                teacher_match,
+                &mut paired,
+            );
+        } else if !moves.values().any(|&j| j == i) {
+            paired.push(change.clone());
+        }
+    }
+    paired
+}
+
+/// Whether the lesson is missing from the new version of its day because nothing was published
+/// for its period, rather than because it was removed.
+///
+/// On exam days, WebUntis often only has entries for the periods with exams and leaves the rest
+/// of the grid empty, so the regular lessons there just vanish. Lessons an entry took the place
+/// of are still reported as removed.
+fn is_unpublished(lesson: &LessonInfo, new_day: &[LessonInfo]) -> bool {
+    let exam_day: bool = new_day.iter().any(|x| x.entry_type == EntryType::Exam);
+    exam_day
+        && !new_day
+            .iter()
+            .any(|x| x.datetime < lesson.end && lesson.datetime < x.end)
+}
+
+fn diff_lesson(
     old: &LessonInfo,
     new: &LessonInfo,
-) -> Result<bool> {
+    teacher_match: TeacherMatch,
+    changes: &mut Vec<LessonChange>,
+) {
     // Cover most common case first
     if old == new {
-        return Ok(false);
+        return;
     }
 
-    if old.status != new.status {
-        let body = format!(
-            "Lesson Status changed from {} to {}.",
-            old.status, new.status,
-        );
-        if matches!(new.status, Status::Cancelled | Status::Removed) {
-            discord.lesson_modification(new, "Lesson Cancellation", &body)?;
-        } else if new.status == Status::Changed {
-            discord.lesson_modification(new, "Lesson Change", &body)?;
+    if is_reinstatement(old.status, new.status) {
+        changes.push(LessonChange::Reinstated {
+            lesson: new.clone(),
+            old_status: old.status,
+        });
+    } else if old.status != new.status && new.status.is_change_worthy() {
+        if new.status.is_cancellation() {
+            changes.push(LessonChange::Cancelled {
+                lesson: new.clone(),
+                old_status: old.status,
+            });
+        } else {
+            changes.push(LessonChange::Changed {
+                lesson: new.clone(),
+                old_status: old.status,
+            });
         }
     }
 
-    if old.subject_status != new.subject_status || old.subject != new.subject {
-        let body = format!(
-            "Subject changed from {} ({}) to {} ({}).",
-            old.subject, old.subject_status, new.subject, new.subject_status
-        );
-        discord.lesson_modification(new, "Subject Changed", &body)?;
+    if old.entry_type != new.entry_type {
+        changes.push(LessonChange::EntryType {
+            lesson: new.clone(),
+            old_entry_type: old.entry_type,
+        });
     }
 
-    if old.teacher_status != new.teacher_status || old.teacher != new.teacher {
-        let body = format!(
-            "Teacher changed from {} ({}) to {} ({}).",
-            old.teacher, old.teacher_status, new.teacher, new.teacher_status
-        );
-        discord.lesson_modification(new, "Teacher Changed", &body)?;
+    if old.subject != new.subject || status_change_worthy(old.subject_status, new.subject_status) {
+        changes.push(LessonChange::Subject {
+            lesson: new.clone(),
+            old_subject: old.subject.clone(),
+            old_status: old.subject_status,
+        });
     }
 
-    if old.room_status != new.room_status || old.room != new.room {
-        let body = format!(
-            "Room changed from {} to {} ({}).",
-            old.room, new.room, new.room_status
-        );
-        discord.lesson_modification(new, "Room Changed", &body)?;
+    if teacher_match.teacher(old) != teacher_match.teacher(new)
+        || status_change_worthy(old.teacher_status, new.teacher_status)
+    {
+        changes.push(LessonChange::Teacher {
+            lesson: new.clone(),
+            old_teacher: old.teacher.clone(),
+            old_status: old.teacher_status,
+            via_teacher: None,
+        });
+    }
+
+    if old.room != new.room || status_change_worthy(old.room_status, new.room_status) {
+        changes.push(LessonChange::Room {
+            lesson: new.clone(),
+            old_room: old.room.clone(),
+        });
+    }
+
+    // Compare times of day only, so lessons on different dates can be compared too.
+    if old.datetime.time() != new.datetime.time() || old.end.time() != new.end.time() {
+        changes.push(LessonChange::Time {
+            lesson: new.clone(),
+            old_start: old.datetime,
+            old_end: old.end,
+        });
     }
 
+    if old.substitution_text != new.substitution_text {
+        changes.push(LessonChange::Substitution {
+            lesson: new.clone(),
+            old_substitution_text: old.substitution_text.clone(),
+        });
+    }
+
+    if old.meeting_url != new.meeting_url {
+        changes.push(LessonChange::MeetingLink {
+            lesson: new.clone(),
+            old_meeting_url: old.meeting_url.clone(),
+        });
+    }
+
+    // Substitution texts are reported on their own (see above).
+    let other_texts = |lesson: &LessonInfo| -> Vec<EntryText> {
+        lesson
+            .texts
+            .iter()
+            .filter(|x| x.text_type != EntryTextType::SubstitutionText)
+            .cloned()
+            .collect()
+    };
     if old.lesson_info != new.lesson_info
         || old.lesson_text != new.lesson_text
-        || old.substitution_text != new.substitution_text
         || old.notes != new.notes
-        || old.texts != new.texts
+        || other_texts(old) != other_texts(new)
     {
-        discord.lesson_modification(new, "Notes Changed", "")?;
+        changes.push(LessonChange::Notes {
+            lesson: new.clone(),
+            old_texts: old.texts.clone(),
+        });
+    }
+}
+
+/// Mention the teacher last reported in a teacher change, if it was neither the baseline's nor
+/// the new one, so a chain of substitutions (A → B → C) reads as one change from A to C.
+fn note_teacher_chain(
+    changes: &mut [LessonChange],
+    base: &LessonInfo,
+    reported: &LessonInfo,
+    teacher_match: TeacherMatch,
+) {
+    for change in changes {
+        if let LessonChange::Teacher {
+            lesson,
+            via_teacher,
+            ..
+        } = change
+        {
+            let via = teacher_match.teacher(reported);
+            if via != teacher_match.teacher(base) && via != teacher_match.teacher(lesson) {
+                via_teacher.clone_from(&reported.teacher);
+            }
+        }
+    }
+}
+
+/// Whether a cancelled (or removed) lesson takes place again.
+const fn is_reinstatement(old: Status, new: Status) -> bool {
+    old.is_cancellation() && matches!(new, Status::Regular | Status::Added)
+}
+
+fn format_time(datetime: NaiveDateTime) -> String {
+    datetime.format("%H:%M").to_string()
+}
+
+fn pair(old: &Option<String>, new: &Option<String>) -> (String, String) {
+    let old = or_absent(old.as_deref()).to_string();
+    let new = or_absent(new.as_deref()).to_string();
+    (old, new)
+}
+
+/// A status change only matters if it enters or leaves a non-normal status.
+fn status_change_worthy(old: Status, new: Status) -> bool {
+    old != new && (old.is_change_worthy() || new.is_change_worthy())
+}
+
+/// Lists which texts were added and removed, labelled by their type.
+fn describe_texts(old: &[EntryText], new: &[EntryText]) -> String {
+    let added = new.iter().filter(|x| !old.contains(x));
+    let removed = old.iter().filter(|x| !new.contains(x));
+
+    let mut lines: Vec<String> = added
+        .map(|x| format!("Added {}: {}", x.text_type, x.text))
+        .collect();
+    lines.extend(removed.map(|x| format!("Removed {}: {}", x.text_type, x.text)));
+    lines.join("\n")
+}
+
+/// Sends a notification for every change of a diff (e.g. from [`diff_lessons`]).
+///
+/// Changes setting work are sent as assignments (see [`AssignmentKeywords::highlight`]).
+/// Changes to lessons that ended before `skip_before` (if given) are not sent.
+pub fn send_potential_diffs(
+    notifier: &dyn Notifier,
+    changes: Vec<LessonChange>,
+    assignment_keywords: &AssignmentKeywords,
+    skip_before: Option<NaiveDateTime>,
+) -> Result<(), Error> {
+    send_diffs_resumable(
+        notifier,
+        changes,
+        assignment_keywords,
+        skip_before,
+        &mut Vec::new(),
+    )
+}
+
+/// Like [`send_potential_diffs`], but skips the changes in `delivered` and adds every change
+/// sent to it, so a diff whose delivery failed partway can be retried without duplicates.
+///
+/// The caller should keep its baseline until this succeeds, and clear `delivered` then.
+pub fn send_diffs_resumable(
+    notifier: &dyn Notifier,
+    changes: Vec<LessonChange>,
+    assignment_keywords: &AssignmentKeywords,
+    skip_before: Option<NaiveDateTime>,
+    delivered: &mut Vec<LessonChange>,
+) -> Result<(), Error> {
+    for change in changes {
+        let change = assignment_keywords.highlight(change);
+        if skip_before.is_some_and(|now| change.is_over(now)) {
+            log::debug!("Skipping change to past lesson: {}", change.title());
+            continue;
+        }
+        if delivered.contains(&change) {
+            log::debug!("Skipping change that was already sent: {}", change.title());
+            continue;
+        }
+        notifier
+            .send_change(&change)
+            .map_err(|e| Error::Http(Cause(e.into())))?;
+        delivered.push(change);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::lesson;
+
+    fn kinds(changes: &[LessonChange]) -> Vec<&'static str> {
+        changes.iter().map(LessonChange::kind).collect()
+    }
+
+    #[test]
+    fn normal_status_changes_are_not_reported() {
+        let old = lesson((8, 0), "Biology");
+        let mut new = old.clone();
+        new.status = Status::NoData;
+        new.room_status = Status::NotAllowed;
+        let changes = diff_lessons(&[old], &[new], TeacherMatch::LongName);
+        assert_eq!(kinds(&changes), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn change_worthy_status_changes_are_reported() {
+        let old = lesson((8, 0), "Biology");
+        let mut new = old.clone();
+        new.status = Status::Cancelled;
+        new.room_status = Status::Changed;
+        let changes = diff_lessons(&[old], &[new], TeacherMatch::LongName);
+        assert_eq!(kinds(&changes), ["cancellation", "room"]);
     }
 
-    Ok(true)
+    #[test]
+    fn lessons_starting_at_the_same_time_are_tracked_independently() {
+        let biology = lesson((8, 0), "Biology");
+        let mut chemistry = lesson((8, 0), "Chemistry");
+        chemistry.room = Some("14".to_string());
+        let old = [biology.clone(), chemistry.clone()];
+
+        let mut cancelled = chemistry;
+        cancelled.status = Status::Cancelled;
+        // The API doesn't keep the order of same-time entries.
+        let new = [cancelled.clone(), biology];
+
+        let changes = diff_lessons(&old, &new, TeacherMatch::LongName);
+        assert_eq!(
+            changes,
+            [LessonChange::Cancelled {
+                lesson: cancelled,
+                old_status: Status::Regular,
+            }]
+        );
+    }
+
+    #[test]
+    fn room_changes_keep_the_key() {
+        let old = lesson((8, 0), "Biology");
+        let mut new = old.clone();
+        new.room = Some("14".to_string());
+        new.room_status = Status::Changed;
+        assert_eq!(old.key(), new.key());
+        assert_eq!(old.key(), "2026-10-19T08:00/Biology");
+
+        let changes = diff_lessons(&[old], &[new], TeacherMatch::LongName);
+        assert_eq!(kinds(&changes), ["room"]);
+    }
+
+    #[test]
+    fn default_assignment_keywords_ignore_generic_words() {
+        let keywords = AssignmentKeywords::default();
+        assert!(keywords.matches("Bitte das Arbeitsblatt 3 bearbeiten"));
+        assert!(keywords.matches("Homework: p. 42"));
+        assert!(!keywords.matches("Task force meeting in the hall"));
+        assert!(!keywords.matches("Exercise in the gym"));
+    }
+
+    #[test]
+    fn moved_lessons_are_reported_as_time_changes() {
+        let old = lesson((8, 0), "Maths");
+        let mut new = lesson((8, 30), "Maths");
+        new.room = Some("14".to_string());
+        let changes = diff_lessons(&[old], &[new], TeacherMatch::LongName);
+        assert_eq!(kinds(&changes), ["room", "time"]);
+    }
+
+    #[test]
+    fn lessons_in_empty_periods_of_an_exam_day_are_not_removed() {
+        let biology = lesson((8, 0), "Biology");
+        let chemistry = lesson((8, 50), "Chemistry");
+        let old = [biology, chemistry, lesson((9, 40), "English")];
+        // Only the exam is published; it takes the place of chemistry.
+        let mut exam = lesson((8, 50), "Maths");
+        exam.entry_type = EntryType::Exam;
+        let new = [exam];
+
+        let changes = diff_lessons(&old, &new, TeacherMatch::LongName);
+        assert_eq!(kinds(&changes), ["entry_type", "subject"]);
+    }
+
+    #[test]
+    fn substitute_subjects_are_reported_as_subject_changes() {
+        let old = lesson((8, 0), "Maths");
+        let mut new = lesson((8, 0), "Physics");
+        new.subject_status = Status::Changed;
+        let changes = diff_lessons(&[old], &[new.clone()], TeacherMatch::LongName);
+        assert_eq!(
+            changes,
+            [LessonChange::Subject {
+                lesson: new,
+                old_subject: Some("Maths".to_string()),
+                old_status: Status::Regular,
+            }]
+        );
+    }
 }
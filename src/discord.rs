@@ -1,45 +1,240 @@
+mod bot;
+mod credentials;
 pub mod embed;
+mod forum;
+mod identity;
+mod lesson_field;
+mod style;
+mod template;
 
-use std::fmt::Write;
+use std::{fmt::Write, time::Duration};
 
-use anyhow::{Context, Result, bail};
-use chrono::{Timelike, Utc};
-use reqwest::{IntoUrl, Url, blocking::Client};
-use serde::Serialize;
+use anyhow::{Context, Result, anyhow, ensure};
+use chrono::{NaiveDate, Timelike, Utc};
+use reqwest::{
+    IntoUrl, StatusCode, Url,
+    blocking::{Client, RequestBuilder, Response},
+    header::AUTHORIZATION,
+};
+use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "image")]
+use crate::timetable_image::TimetableRenderer;
 use crate::{
-    LessonInfo,
+    DateFormat, LessonChange, LessonInfo, TlsConfig,
     discord::embed::{Color, Embed, Field},
-    validate,
+    notify::published_description,
+    or_absent,
+    untis::{exams::ExamChange, messages::DayMessage},
 };
 
+pub use bot::BotCredentials;
+pub use credentials::WebhookCredentials;
+pub use forum::Forum;
+pub use identity::{Identities, Identity};
+pub use lesson_field::LessonField;
+pub use style::NotifyStyle;
+pub use template::Template;
+
 #[derive(Debug, Clone)]
 pub struct DiscordClient {
     http_client: Client,
     url: Url,
+    /// Set when posting as a bot instead of through a webhook.
+    bot: Option<BotCredentials>,
+    forum: Option<Forum>,
+    lesson_fields: Vec<LessonField>,
+    style: NotifyStyle,
+    /// Prefixed to every title, to tell apart several bots posting to the same channel.
+    label: Option<String>,
+    /// Usernames and avatars per change kind, instead of the default ones.
+    identities: Identities,
+    title_template: Template,
+    body_template: Template,
+    date_format: DateFormat,
+    tls: TlsConfig,
+    timeout: Duration,
+    /// Renders the image attached to timetable messages, if wanted.
+    #[cfg(feature = "image")]
+    renderer: Option<TimetableRenderer>,
 }
 
+/// How long a webhook request may take, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a webhook request is tried before giving up on transient errors.
+const SEND_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const LESSON_COLOR: Color = Color::new(146, 23, 237);
+
+/// Discord rejects forum posts with longer thread names.
+const MAX_THREAD_NAME_CHARS: usize = 100;
+
 const LOGO_IMAGE_URL: &str =
     "https://cdn.aptoide.com/imgs/b/1/3/b1399c00075a847dd4e54baddfa11b45_icon.png";
 
 #[derive(Debug, Clone, Serialize)]
 struct WebhookRequest<'a> {
-    username: &'a str,
-    avatar_url: &'a str,
+    /// Only webhooks can pick their username and avatar per message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
     embeds: Vec<Embed<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    applied_tags: Vec<String>,
 }
 
 impl DiscordClient {
     pub fn new(webhook_url: impl IntoUrl) -> Result<Self> {
         let url = webhook_url.into_url().context("Invalid WebHook URL")?;
-        validate_url(&url).context("Invalid WebHook URL")?;
-        let http_client = Client::new();
-        Ok(Self { http_client, url })
+        let credentials = WebhookCredentials::from_url(&url).context("Invalid WebHook URL")?;
+        Self::from_credentials(&credentials)
     }
 
     pub fn from_parts(id: u64, token: &str) -> Result<Self> {
-        let url = format!("https://discord.com/api/webhooks/{id}/{token}");
-        Self::new(url)
+        let credentials = WebhookCredentials::new(id, token).context("Invalid WebHook token")?;
+        Self::from_credentials(&credentials)
+    }
+
+    pub fn from_credentials(credentials: &WebhookCredentials) -> Result<Self> {
+        Self::with_target(credentials.to_url(), None)
+    }
+
+    /// Post as a bot to the given channel instead of through a webhook.
+    pub fn from_bot(token: &str, channel_id: u64) -> Result<Self> {
+        let credentials = BotCredentials::new(token, channel_id)?;
+        Self::with_target(credentials.to_url(), Some(credentials))
+    }
+
+    fn with_target(url: Url, bot: Option<BotCredentials>) -> Result<Self> {
+        let tls = TlsConfig::new();
+        let http_client = build_http_client(&tls, DEFAULT_TIMEOUT)?;
+        Ok(Self {
+            http_client,
+            url,
+            bot,
+            forum: None,
+            lesson_fields: LessonField::ALL.to_vec(),
+            style: NotifyStyle::default(),
+            label: None,
+            identities: Identities::default(),
+            title_template: Template::default_title(),
+            body_template: Template::default_body(),
+            date_format: DateFormat::default(),
+            tls,
+            timeout: DEFAULT_TIMEOUT,
+            #[cfg(feature = "image")]
+            renderer: None,
+        })
+    }
+
+    /// Post every notification as a new thread, for webhooks targeting a forum channel.
+    #[must_use]
+    pub fn with_forum(mut self, forum: Forum) -> Self {
+        self.forum = Some(forum);
+        self
+    }
+
+    /// Use custom TLS settings for all requests to Discord.
+    pub fn with_tls(mut self, tls: &TlsConfig) -> Result<Self> {
+        self.http_client = build_http_client(tls, self.timeout)?;
+        self.tls = tls.clone();
+        Ok(self)
+    }
+
+    /// Give up on requests to Discord that take longer than this.
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Self> {
+        self.http_client = build_http_client(&self.tls, timeout)?;
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    /// Render the title and description of change notifications from these templates.
+    #[must_use]
+    pub fn with_templates(mut self, title: Template, body: Template) -> Self {
+        self.title_template = title;
+        self.body_template = body;
+        self
+    }
+
+    /// Show lesson dates in this format.
+    #[must_use]
+    pub fn with_date_format(mut self, date_format: DateFormat) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    /// Render change notifications in this style.
+    #[must_use]
+    pub const fn with_style(mut self, style: NotifyStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Attach an image of the timetable, rendered by this renderer, to timetable messages.
+    #[cfg(feature = "image")]
+    #[must_use]
+    pub fn with_timetable_image(mut self, renderer: TimetableRenderer) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+
+    /// Prefix every title with the label in brackets, e.g. `[10B] Room Changed`.
+    #[must_use]
+    pub fn with_label(mut self, label: String) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Post notifications about changes of some kinds with a different username and avatar.
+    #[must_use]
+    pub fn with_identities(mut self, identities: Identities) -> Self {
+        self.identities = identities;
+        self
+    }
+
+    /// The username to post messages about changes of this kind with (none for bots).
+    fn username(&self, kind: Option<&str>) -> Option<&str> {
+        if self.bot.is_some() {
+            return None;
+        }
+        let username = self
+            .identities
+            .get(kind)
+            .map_or("WebUntis", |x| x.username.as_str());
+        Some(username)
+    }
+
+    fn avatar_url(&self, kind: Option<&str>) -> Option<&str> {
+        if self.bot.is_some() {
+            return None;
+        }
+        let url = self
+            .identities
+            .get(kind)
+            .and_then(|x| x.avatar_url.as_ref())
+            .map_or(LOGO_IMAGE_URL, Url::as_str);
+        Some(url)
+    }
+
+    /// The title with the label (if any) in front.
+    fn labelled(&self, title: &str) -> String {
+        self.label
+            .as_ref()
+            .map_or_else(|| title.to_string(), |label| format!("[{label}] {title}"))
+    }
+
+    /// Only include the given standard lesson fields (in this order) in lesson embeds.
+    #[must_use]
+    pub fn with_lesson_fields(mut self, fields: Vec<LessonField>) -> Self {
+        self.lesson_fields = fields;
+        self
     }
 
     fn send_embed(
@@ -48,64 +243,422 @@ impl DiscordClient {
         content: &str,
         color: Color,
         fields: Vec<Field>,
-    ) -> Result<()> {
+        thread_name: &str,
+        kind: Option<&str>,
+    ) -> Result<StatusCode> {
+        let title = self.labelled(title);
+        let thread_name = truncate_thread_name(&self.labelled(thread_name));
         let embed = Embed {
-            title,
+            title: &title,
             description: content,
             color,
             timestamp: Utc::now(),
             fields,
         };
         let body = WebhookRequest {
-            username: "WebUntis",
-            avatar_url: LOGO_IMAGE_URL,
+            username: self.username(kind),
+            avatar_url: self.avatar_url(kind),
+            content: None,
             embeds: vec![embed],
+            thread_name: self.forum.as_ref().map(|_| thread_name.as_str()),
+            applied_tags: self
+                .forum
+                .as_ref()
+                .map_or_else(Vec::new, |f| f.tags_for(kind)),
         };
-        let resp = self.http_client.post(self.url.clone()).json(&body).send()?;
-        resp.error_for_status()?;
+        self.post_with_retry(&body)
+    }
+
+    /// Send a plain text message without an embed.
+    fn send_text(&self, content: &str, thread_name: &str, kind: Option<&str>) -> Result<()> {
+        let content = self.labelled(content);
+        let thread_name = truncate_thread_name(&self.labelled(thread_name));
+        let body = WebhookRequest {
+            username: self.username(kind),
+            avatar_url: self.avatar_url(kind),
+            content: Some(&content),
+            embeds: vec![],
+            thread_name: self.forum.as_ref().map(|_| thread_name.as_str()),
+            applied_tags: self
+                .forum
+                .as_ref()
+                .map_or_else(Vec::new, |f| f.tags_for(kind)),
+        };
+        self.post_with_retry(&body)?;
         Ok(())
     }
 
+    /// Post to the webhook, retrying a few times on server errors and connection problems.
+    ///
+    /// Client errors (e.g. a rejected payload) fail immediately, since retrying won't help.
+    fn post_with_retry(&self, body: &WebhookRequest<'_>) -> Result<StatusCode> {
+        let resp = self.send_with_retry(|| self.post().json(body))?;
+        Ok(resp.status())
+    }
+
+    /// A POST request to the webhook or channel, authorized if posting as a bot.
+    fn post(&self) -> RequestBuilder {
+        self.authorized(self.http_client.post(self.url.clone()))
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.bot {
+            Some(bot) => request.header(AUTHORIZATION, bot.authorization()),
+            None => request,
+        }
+    }
+
+    /// Post the message with a PNG file attached, retrying like [`Self::post_with_retry`].
+    #[cfg(feature = "image")]
+    fn post_with_image(
+        &self,
+        body: &WebhookRequest<'_>,
+        file_name: &str,
+        png: &[u8],
+    ) -> Result<StatusCode> {
+        use reqwest::blocking::multipart::{Form, Part};
+
+        let payload = serde_json::to_string(body).context("Could not serialize webhook request")?;
+        let resp = self.send_with_retry(|| {
+            let part = Part::bytes(png.to_vec())
+                .file_name(file_name.to_string())
+                .mime_str("image/png")
+                .expect("image/png is a valid MIME type");
+            let form = Form::new()
+                .text("payload_json", payload.clone())
+                .part("files[0]", part);
+            self.post().multipart(form)
+        })?;
+        Ok(resp.status())
+    }
+
+    /// Send the request built by `request`, retrying a few times on transient errors.
+    fn send_with_retry(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut attempt: u32 = 1;
+        loop {
+            let (error, transient): (anyhow::Error, bool) = match request().send() {
+                Ok(resp) if resp.status().is_success() => return Ok(resp),
+                Ok(resp) => {
+                    let status = resp.status();
+                    (api_error(resp), status.is_server_error())
+                }
+                Err(e) => {
+                    let transient = is_transient(&e);
+                    (e.into(), transient)
+                }
+            };
+            if !transient || attempt >= SEND_ATTEMPTS {
+                return Err(error);
+            }
+            log::warn!(
+                "Sending to Discord failed (attempt {attempt}/{SEND_ATTEMPTS}); retrying in {RETRY_DELAY:?}: {error:#}"
+            );
+            std::thread::sleep(RETRY_DELAY);
+            attempt += 1;
+        }
+    }
+
+    /// Send a single test message, returning the HTTP status Discord responded with.
+    pub fn send_test(&self) -> Result<StatusCode> {
+        let title = "Webhook test OK";
+        let content = "This WebHook is ready to receive WebUntis notifications.";
+        let color = Color::new(67, 181, 129);
+        self.send_embed(title, content, color, vec![], title, None)
+    }
+
     pub fn send_error(&self, err_message: &str) {
         log::error!("{err_message}");
 
         let title = "Internal Error";
         let color = Color::new(228, 24, 17);
-        if let Err(e) = self.send_embed(title, err_message, color, vec![]) {
+        if let Err(e) = self.send_embed(title, err_message, color, vec![], title, None) {
             log::error!("Sending error message to webhook failed: {e}");
         }
     }
 
+    pub fn send_warning(&self, message: &str) {
+        log::warn!("{message}");
+
+        let title = "Warning";
+        let color = Color::new(250, 166, 26);
+        if let Err(e) = self.send_embed(title, message, color, vec![], title, None) {
+            log::error!("Sending warning message to webhook failed: {e}");
+        }
+    }
+
+    /// Send all of a day's changes as a single message.
+    pub fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<()> {
+        let title = format!("Timetable digest for {date}");
+        let content = if changes.is_empty() {
+            "No changes to the timetable.".to_string()
+        } else {
+            changes
+                .iter()
+                .map(LessonChange::summary_line)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        self.send_embed(&title, &content, LESSON_COLOR, vec![], &title, None)?;
+        Ok(())
+    }
+
+    /// Send a day's whole timetable as a single message.
+    ///
+    /// If a renderer is set, an image of the timetable is attached to the message.
+    pub fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<()> {
+        let title = format!("Timetable for {date}");
+        #[cfg(feature = "image")]
+        if let Some(renderer) = &self.renderer {
+            let title = self.labelled(&title);
+            let png: Vec<u8> = renderer.render_png(&title, lessons)?;
+            let body = WebhookRequest {
+                username: self.username(None),
+                avatar_url: self.avatar_url(None),
+                content: None,
+                embeds: vec![],
+                thread_name: self.forum.as_ref().map(|_| title.as_str()),
+                applied_tags: self
+                    .forum
+                    .as_ref()
+                    .map_or_else(Vec::new, |f| f.tags_for(None)),
+            };
+            self.post_with_image(&body, &format!("timetable-{date}.png"), &png)?;
+            return Ok(());
+        }
+        let content = timetable_content(lessons);
+        self.send_embed(&title, &content, LESSON_COLOR, vec![], &title, None)?;
+        Ok(())
+    }
+
+    /// Post a day's whole timetable as a message that can be edited later on, returning its ID.
+    ///
+    /// Only bots can edit their messages, so this fails for webhooks.
+    pub fn post_timetable_message(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<u64> {
+        ensure!(self.bot.is_some(), "Only bots can edit their messages");
+        let title = self.labelled(&format!("Timetable for {date}"));
+        let content = timetable_content(lessons);
+        let body = self.timetable_message(&title, &content);
+        let resp = self.send_with_retry(|| self.post().json(&body))?;
+        let message: Message = resp
+            .json()
+            .context("Invalid message in Discord's response")?;
+        message.id.parse().context("Invalid message ID")
+    }
+
+    /// Replace a message posted by [`Self::post_timetable_message`] with a day's timetable.
+    ///
+    /// Returns whether the message still exists; if it was deleted, nothing is edited.
+    pub fn edit_timetable_message(
+        &self,
+        message_id: u64,
+        date: NaiveDate,
+        lessons: &[LessonInfo],
+    ) -> Result<bool> {
+        ensure!(self.bot.is_some(), "Only bots can edit their messages");
+        let title = self.labelled(&format!("Timetable for {date}"));
+        let content = timetable_content(lessons);
+        let body = self.timetable_message(&title, &content);
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .expect("HTTPS URLs have a path")
+            .push(&message_id.to_string());
+        // Not retried, since the next iteration edits the message again anyway.
+        let resp = self
+            .authorized(self.http_client.patch(url))
+            .json(&body)
+            .send()?;
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            _ => Err(api_error(resp)),
+        }
+    }
+
+    fn timetable_message<'a>(&'a self, title: &'a str, content: &'a str) -> WebhookRequest<'a> {
+        let embed = Embed {
+            title,
+            description: content,
+            color: LESSON_COLOR,
+            timestamp: Utc::now(),
+            fields: vec![],
+        };
+        WebhookRequest {
+            username: self.username(None),
+            avatar_url: self.avatar_url(None),
+            content: None,
+            embeds: vec![embed],
+            thread_name: None,
+            applied_tags: vec![],
+        }
+    }
+
+    /// Send a notification about an added, changed or removed exam.
+    pub fn send_exam_change(&self, change: &ExamChange) -> Result<()> {
+        let title = change.title();
+        let color = match change {
+            ExamChange::Removed { .. } => Color::new(67, 181, 129),
+            _ => Color::new(250, 166, 26),
+        };
+        let exam = change.exam();
+        let teachers = exam.teachers.join(", ");
+        let mut fields = vec![Field::new("Subject", &exam.subject)];
+        if !teachers.is_empty() {
+            fields.push(Field::new("Teachers", &teachers));
+        }
+        let mut content = change.description();
+        if !exam.name.is_empty() {
+            content = format!("**{}**\n{content}", exam.name);
+        }
+        if !exam.text.is_empty() {
+            content.push_str(&format!("\n{}", exam.text));
+        }
+        self.send_embed(title, &content, color, fields, title, None)?;
+        Ok(())
+    }
+
+    /// Send a notification that the timetable of previously empty days was published.
+    pub fn send_published(&self, dates: &[NaiveDate]) -> Result<()> {
+        let title = "New Timetable Published";
+        let content = published_description(dates);
+        self.send_embed(title, &content, LESSON_COLOR, vec![], title, None)?;
+        Ok(())
+    }
+
+    /// Send a school-wide message of the day.
+    pub fn send_day_message(&self, message: &DayMessage) -> Result<()> {
+        let title = if message.subject.is_empty() {
+            "Message of the Day"
+        } else {
+            &message.subject
+        };
+        let content = message.plain_text();
+        let color = Color::new(250, 166, 26);
+        self.send_embed(title, &content, color, vec![], title, None)?;
+        Ok(())
+    }
+
     pub fn lesson_modification(&self, info: &LessonInfo, title: &str, content: &str) -> Result<()> {
+        self.send_lesson_embed(info, title, content, lesson_color(info), None, vec![])
+    }
+
+    /// Send a notification about the change, tagged with its kind if posting to a forum.
+    ///
+    /// Changes with a before/after value get these as a pair of inline fields.
+    /// The title and description are rendered from the client's templates.
+    pub fn send_change(&self, change: &LessonChange) -> Result<()> {
+        if self.style == NotifyStyle::Compact {
+            return self.send_text(
+                &style::compact_line(change),
+                &lesson_thread_name(change.title(), change.lesson()),
+                Some(change.kind()),
+            );
+        }
+        let before_after = change.before_after();
+        let mut change_fields = match &before_after {
+            Some((before, after)) => vec![
+                Field::new("Before", before),
+                Field::new("After", after),
+                Field::blank(),
+            ],
+            None => vec![],
+        };
+        let meeting_link: Option<String> = match change {
+            LessonChange::MeetingLink { lesson, .. } => lesson
+                .meeting_url
+                .as_ref()
+                .map(|url| format!("[Join the meeting]({url})")),
+            _ => None,
+        };
+        if let Some(link) = &meeting_link {
+            change_fields.push(Field::new("Meeting", link));
+        }
+        let color = match change {
+            // Good news gets the same green as successful tests.
+            LessonChange::Reinstated { .. } => Color::new(67, 181, 129),
+            LessonChange::Substitution { .. }
+            | LessonChange::MeetingLink { .. }
+            | LessonChange::Assignment { .. } => Color::new(52, 152, 219),
+            _ => lesson_color(change.lesson()),
+        };
+        self.send_lesson_embed(
+            change.lesson(),
+            &self.title_template.render(change, &self.date_format),
+            &self.body_template.render(change, &self.date_format),
+            color,
+            Some(change.kind()),
+            change_fields,
+        )
+    }
+
+    fn send_lesson_embed(
+        &self,
+        info: &LessonInfo,
+        title: &str,
+        content: &str,
+        color: Color,
+        kind: Option<&str>,
+        change_fields: Vec<Field>,
+    ) -> Result<()> {
         log::info!(
             "Sending lesson modification regarding {} at {}",
-            info.subject,
+            or_absent(info.subject.as_deref()),
             info.datetime,
         );
 
         let time = info.datetime.time();
         let time = format!("{:02}:{:02}", time.hour(), time.minute());
+        // Students think in periods rather than clock times.
+        let time_label = info
+            .period
+            .map_or_else(|| time.clone(), |x| format!("Period {x} ({time})"));
+
+        let link = info
+            .web_url
+            .as_ref()
+            .map(|url| format!("[Open in WebUntis]({url})"));
 
-        let fields = vec![
-            Field::new("Subject", &info.subject),
-            Field::new("Teacher", &info.teacher),
-            Field::new("Room", &info.room),
-            Field::new("Time", &time),
-        ];
+        let room_label = info.room_label();
+        let mut fields = change_fields;
+        for &field in &self.lesson_fields {
+            let value = match field {
+                LessonField::Subject => info.subject.as_deref(),
+                LessonField::Class => info.class.as_deref(),
+                LessonField::Teacher => info.teacher.as_deref(),
+                LessonField::Room => room_label.as_deref(),
+                LessonField::Time => Some(time_label.as_str()),
+            };
+            // Leave out absent values instead of cluttering the embed with placeholders.
+            if let Some(value) = value.filter(|x| !x.is_empty()) {
+                fields.push(Field::new(field.label(), value));
+            }
+        }
+        if let Some(link) = &link {
+            fields.push(Field::new("Timetable", link));
+        }
 
-        let mut content = format!("({})\n**{}**\n", info.datetime, content);
+        let date = self.date_format.format(info.datetime);
+        let mut content = format!("({date})\n**{content}**\n");
         let mut push = |a, b| push_content(&mut content, a, b);
+        push("Group", info.group.as_deref());
+        push("Info", info.info.as_deref());
         push("Lesson Info", info.lesson_info.as_deref());
         push("Lesson Text", info.lesson_text.as_deref());
         push("Substitution Text", info.substitution_text.as_deref());
         push("Notes", info.notes.as_deref());
         for (i, text) in info.texts.iter().enumerate() {
-            let _ = writeln!(content, "**Text #{}:** {}", i + 1, text);
+            let _ = writeln!(
+                content,
+                "**Text #{} ({}):** {}",
+                i + 1,
+                text.text_type,
+                text.text
+            );
         }
 
-        let color = Color::new(146, 23, 237);
-        self.send_embed(title, &content, color, fields)
-            .context("sending lesson modification info")
+        let thread_name = lesson_thread_name(title, info);
+        self.send_embed(title, &content, color, fields, &thread_name, kind)
+            .context("sending lesson modification info")?;
+        Ok(())
     }
 }
 
@@ -115,29 +668,88 @@ fn push_content(buffer: &mut String, label: &'static str, maybe_str: Option<&str
     }
 }
 
-fn validate_url(url: &Url) -> Result<()> {
-    assert_url_part("Scheme", "https", url.scheme())?;
-    assert_url_part("Host", "discord.com", url.host_str().unwrap_or(""))?;
-    let segments = url.path_segments().map_or(vec![], |x| x.collect());
-    if segments.len() != 4 {
-        bail!("Expected 4 URL path segments, got {}", segments.len());
-    }
-    assert_url_part("Segment #1", "api", segments[0])?;
-    assert_url_part("Segment #2", "webhooks", segments[1])?;
-    segments[2].parse::<u64>().context("Invalid Webhook ID")?;
-    validate::generic_token(segments[3])?;
-    if let Some(query) = url.query() {
-        bail!("Expected no query, got {query}");
+/// The name of the forum thread for a lesson notification, e.g. `Room Changed: Math (08:00)`.
+fn lesson_thread_name(title: &str, info: &LessonInfo) -> String {
+    format!(
+        "{title}: {} ({})",
+        or_absent(info.subject.as_deref()),
+        info.datetime.format("%H:%M")
+    )
+}
+
+/// Shortens the name to what Discord accepts for a thread, marking the cut with `…`.
+fn truncate_thread_name(name: &str) -> String {
+    if name.chars().count() <= MAX_THREAD_NAME_CHARS {
+        return name.to_string();
     }
-    if let Some(frag) = url.fragment() {
-        bail!("Expected no fragment, got {frag}");
+    let mut truncated: String = name.chars().take(MAX_THREAD_NAME_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// One line per lesson, for messages showing a whole day.
+fn timetable_content(lessons: &[LessonInfo]) -> String {
+    if lessons.is_empty() {
+        return "No lessons.".to_string();
     }
-    Ok(())
+    lessons
+        .iter()
+        .map(LessonInfo::summary_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The school's color for the lesson's subject, if it has one.
+fn lesson_color(info: &LessonInfo) -> Color {
+    info.subject_color
+        .as_deref()
+        .and_then(|hex| Color::from_hex(hex).ok())
+        .unwrap_or(LESSON_COLOR)
 }
 
-fn assert_url_part(label: &'static str, expected: &'static str, actual: &str) -> Result<()> {
-    if expected != actual {
-        bail!("URL {label} is {actual:?} instead of {expected:?}");
+/// Whether the request error is likely to go away when trying again (network problems).
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request()
+}
+
+/// The part of a message Discord sends back when it was posted.
+#[derive(Debug, Deserialize)]
+struct Message {
+    /// A snowflake, sent as a string.
+    id: String,
+}
+
+/// The error body Discord sends along with a non-success status.
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+    code: i64,
+    /// Per-field details about what is wrong with the payload.
+    errors: Option<serde_json::Value>,
+}
+
+/// Describe a failed response, including Discord's explanation of what was wrong (if any).
+fn api_error(resp: Response) -> anyhow::Error {
+    let status = resp.status();
+    let text = resp.text().unwrap_or_default();
+    match serde_json::from_str::<ApiError>(&text) {
+        Ok(ApiError {
+            message,
+            code,
+            errors: Some(errors),
+        }) => anyhow!("Discord responded with {status}: {message} (code {code}); errors: {errors}"),
+        Ok(ApiError { message, code, .. }) => {
+            anyhow!("Discord responded with {status}: {message} (code {code})")
+        }
+        Err(_) if text.trim().is_empty() => anyhow!("Discord responded with {status}"),
+        Err(_) => anyhow!("Discord responded with {status}: {}", text.trim()),
     }
-    Ok(())
+}
+
+/// An HTTP client for talking to Discord with the given TLS settings and request timeout.
+fn build_http_client(tls: &TlsConfig, timeout: Duration) -> Result<Client> {
+    tls.apply(Client::builder())
+        .timeout(timeout)
+        .build()
+        .context("Could not build HTTP client")
 }
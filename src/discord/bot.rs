@@ -0,0 +1,58 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use reqwest::Url;
+
+use crate::validate;
+
+/// The token of a Discord bot and the channel it posts to, validated on creation.
+///
+/// Unlike webhooks, a bot can edit its messages later on.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BotCredentials {
+    token: String,
+    channel_id: u64,
+}
+
+impl BotCredentials {
+    pub fn new(token: impl Into<String>, channel_id: u64) -> Result<Self> {
+        let token = token.into();
+        validate::discord_bot_token(&token).context("Invalid Discord bot token")?;
+        Ok(Self { token, channel_id })
+    }
+
+    #[must_use]
+    pub const fn channel_id(&self) -> u64 {
+        self.channel_id
+    }
+
+    /// The URL to post messages to.
+    #[must_use]
+    pub fn to_url(&self) -> Url {
+        let mut url = Url::parse("https://discord.com").expect("constant URL is valid");
+        url.path_segments_mut()
+            .expect("HTTPS URLs have a path")
+            .extend([
+                "api",
+                "v10",
+                "channels",
+                &self.channel_id.to_string(),
+                "messages",
+            ]);
+        url
+    }
+
+    /// The value of the `Authorization` header.
+    pub(super) fn authorization(&self) -> String {
+        format!("Bot {}", self.token)
+    }
+}
+
+/// Keeps the token out of logs.
+impl fmt::Debug for BotCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BotCredentials")
+            .field("channel_id", &self.channel_id)
+            .finish_non_exhaustive()
+    }
+}
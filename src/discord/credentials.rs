@@ -0,0 +1,73 @@
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use reqwest::Url;
+
+use crate::validate;
+
+/// The ID and token identifying a Discord webhook, validated on creation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WebhookCredentials {
+    id: u64,
+    token: String,
+}
+
+impl WebhookCredentials {
+    pub fn new(id: u64, token: impl Into<String>) -> Result<Self> {
+        let token = token.into();
+        validate::generic_token(&token)?;
+        Ok(Self { id, token })
+    }
+
+    /// Extract the credentials from a webhook URL like
+    /// `https://discord.com/api/webhooks/<id>/<token>`.
+    pub fn from_url(url: &Url) -> Result<Self> {
+        assert_url_part("Scheme", "https", url.scheme())?;
+        assert_url_part("Host", "discord.com", url.host_str().unwrap_or(""))?;
+        let segments = url.path_segments().map_or(vec![], |x| x.collect());
+        if segments.len() != 4 {
+            bail!("Expected 4 URL path segments, got {}", segments.len());
+        }
+        assert_url_part("Segment #1", "api", segments[0])?;
+        assert_url_part("Segment #2", "webhooks", segments[1])?;
+        if let Some(query) = url.query() {
+            bail!("Expected no query, got {query}");
+        }
+        if let Some(frag) = url.fragment() {
+            bail!("Expected no fragment, got {frag}");
+        }
+        let id = segments[2].parse::<u64>().context("Invalid Webhook ID")?;
+        Self::new(id, segments[3])
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The URL to post to.
+    #[must_use]
+    pub fn to_url(&self) -> Url {
+        let mut url = Url::parse("https://discord.com").expect("constant URL is valid");
+        url.path_segments_mut()
+            .expect("HTTPS URLs have a path")
+            .extend(["api", "webhooks", &self.id.to_string(), &self.token]);
+        url
+    }
+}
+
+/// Keeps the token out of logs.
+impl fmt::Debug for WebhookCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookCredentials")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+fn assert_url_part(label: &'static str, expected: &'static str, actual: &str) -> Result<()> {
+    if expected != actual {
+        bail!("URL {label} is {actual:?} instead of {expected:?}");
+    }
+    Ok(())
+}
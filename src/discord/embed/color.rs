@@ -1,4 +1,7 @@
-use serde::{Serialize, Serializer};
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result, bail};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
@@ -13,6 +16,17 @@ impl Color {
         Self { r, g, b }
     }
 
+    /// Parses a color in the `#RRGGBB` or `RRGGBB` format (case-insensitive).
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+            bail!("Invalid hex color {hex:?}; expected #RRGGBB");
+        }
+        let rgb = u32::from_str_radix(digits, 16).context("Invalid hex color")?;
+        let [_, r, g, b] = rgb.to_be_bytes();
+        Ok(Self::new(r, g, b))
+    }
+
     #[must_use]
     pub fn to_discord(self) -> u32 {
         let r = u32::from(self.r);
@@ -22,8 +36,55 @@ impl Color {
     }
 }
 
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(string)
+    }
+}
+
 impl Serialize for Color {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_u32(self.to_discord())
     }
 }
+
+/// Deserializes from a hex string (see [`Color::from_hex`]), as written in config files.
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Self::from_hex(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_with_and_without_hash() {
+        assert_eq!(
+            Color::from_hex("#1a2B3c").unwrap(),
+            Color::new(0x1a, 0x2b, 0x3c)
+        );
+        assert_eq!(
+            "1A2b3C".parse::<Color>().unwrap(),
+            Color::new(0x1a, 0x2b, 0x3c)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_lengths() {
+        for hex in ["", "#", "#fff", "12345", "1234567", "#1234567"] {
+            assert!(Color::from_hex(hex).is_err(), "{hex:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_non_hex_digits() {
+        for hex in ["#12345g", "zzzzzz", "+12345", "#-12345", "12 345"] {
+            assert!(Color::from_hex(hex).is_err(), "{hex:?}");
+        }
+    }
+}
@@ -16,4 +16,10 @@ impl<'a> Field<'a> {
             inline: true,
         }
     }
+
+    /// An empty inline field, used to pad a row so the following fields start on a new one.
+    #[must_use]
+    pub const fn blank() -> Self {
+        Self::new("\u{200b}", "\u{200b}")
+    }
 }
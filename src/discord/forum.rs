@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+/// Settings for webhooks targeting a Discord forum channel.
+///
+/// Every notification becomes its own forum post; the post's tags are chosen by change kind
+/// (see [`crate::LessonChange::kind`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Forum {
+    tags: HashMap<String, u64>,
+}
+
+impl Forum {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the forum tag with the given ID to all posts about changes of the given kind.
+    #[must_use]
+    pub fn tag(mut self, kind: impl Into<String>, tag_id: u64) -> Self {
+        self.tags.insert(kind.into(), tag_id);
+        self
+    }
+
+    pub(super) fn tags_for(&self, kind: Option<&str>) -> Vec<String> {
+        kind.and_then(|k| self.tags.get(k))
+            .map(ToString::to_string)
+            .into_iter()
+            .collect()
+    }
+}
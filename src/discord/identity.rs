@@ -0,0 +1,76 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{Context, Error, Result, ensure};
+use reqwest::Url;
+use serde::{Serialize, Serializer};
+
+/// The username and avatar a webhook message is posted with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub username: String,
+    pub avatar_url: Option<Url>,
+}
+
+/// Parses `<username>` or `<username>|<avatar URL>`.
+impl FromStr for Identity {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        let (username, avatar_url) = match string.split_once('|') {
+            Some((username, url)) => {
+                let url = Url::parse(url.trim()).context("Invalid avatar URL")?;
+                (username, Some(url))
+            }
+            None => (string, None),
+        };
+        let username = username.trim();
+        ensure!(!username.is_empty(), "Missing username");
+        Ok(Self {
+            username: username.to_string(),
+            avatar_url,
+        })
+    }
+}
+
+/// Writes `<username>` or `<username>|<avatar URL>`, as parsed.
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.username)?;
+        if let Some(url) = &self.avatar_url {
+            write!(f, "|{url}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes as written (see the [`Display`](fmt::Display) impl).
+impl Serialize for Identity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Per change kind (see [`crate::LessonChange::kind`]) identities to post with instead of the
+/// default one, e.g. a red icon for cancellations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identities {
+    by_kind: HashMap<String, Identity>,
+}
+
+impl Identities {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Post notifications about changes of the given kind with this identity.
+    #[must_use]
+    pub fn with(mut self, kind: impl Into<String>, identity: Identity) -> Self {
+        self.by_kind.insert(kind.into(), identity);
+        self
+    }
+
+    pub(super) fn get(&self, kind: Option<&str>) -> Option<&Identity> {
+        kind.and_then(|k| self.by_kind.get(k))
+    }
+}
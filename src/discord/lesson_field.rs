@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+use anyhow::{Error, bail};
+use serde::Serialize;
+
+/// One of the standard fields describing the affected lesson in a notification embed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LessonField {
+    Subject,
+    /// The attending classes; only known when watching a teacher's timetable.
+    Class,
+    Teacher,
+    Room,
+    Time,
+}
+
+impl LessonField {
+    /// All fields, in the order they appear in an embed.
+    pub const ALL: [Self; 5] = [
+        Self::Subject,
+        Self::Class,
+        Self::Teacher,
+        Self::Room,
+        Self::Time,
+    ];
+
+    /// The field's name as shown in the embed.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Subject => "Subject",
+            Self::Class => "Class",
+            Self::Teacher => "Teacher",
+            Self::Room => "Room",
+            Self::Time => "Time",
+        }
+    }
+}
+
+impl FromStr for LessonField {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Ok(match string.to_ascii_lowercase().as_str() {
+            "subject" => Self::Subject,
+            "class" => Self::Class,
+            "teacher" => Self::Teacher,
+            "room" => Self::Room,
+            "time" => Self::Time,
+            _ => bail!(
+                "Unknown embed field {string:?}; expected subject, class, teacher, room or time"
+            ),
+        })
+    }
+}
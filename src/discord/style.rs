@@ -0,0 +1,59 @@
+use std::str::FromStr;
+
+use anyhow::{Error, bail};
+use serde::Serialize;
+
+use crate::{LessonChange, or_absent};
+
+/// How change notifications are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyStyle {
+    /// An embed with the lesson's details as fields.
+    #[default]
+    Detailed,
+    /// A single line of text per change, for quick scanning on small screens.
+    Compact,
+}
+
+impl FromStr for NotifyStyle {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Ok(match string.to_ascii_lowercase().as_str() {
+            "detailed" => Self::Detailed,
+            "compact" => Self::Compact,
+            _ => bail!("Unknown notification style {string:?}; expected detailed or compact"),
+        })
+    }
+}
+
+/// The change as a single line, e.g. `❌ 08:00 Math (Room 12) — cancelled`.
+pub(super) fn compact_line(change: &LessonChange) -> String {
+    let lesson = change.lesson();
+    let icon = match change {
+        LessonChange::Cancelled { .. } | LessonChange::Removed { .. } => "❌",
+        LessonChange::Reinstated { .. } | LessonChange::Reverted { .. } => "✅",
+        LessonChange::Added { .. } => "➕",
+        LessonChange::Assignment { .. } => "📝",
+        _ => "🔄",
+    };
+    let room = lesson
+        .room
+        .as_ref()
+        .map_or_else(String::new, |x| format!(" ({x})"));
+    let what = match change {
+        LessonChange::Cancelled { .. }
+        | LessonChange::Changed { .. }
+        | LessonChange::Reinstated { .. } => lesson.status.to_string().to_lowercase(),
+        _ => match change.before_after() {
+            Some((before, after)) => format!("{}: {before} → {after}", change.title()),
+            None => change.title().to_string(),
+        },
+    };
+    format!(
+        "{icon} {} {}{room} — {what}",
+        lesson.datetime.format("%H:%M"),
+        or_absent(lesson.subject.as_deref()),
+    )
+}
@@ -0,0 +1,178 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Error, Result, bail};
+use serde::{Serialize, Serializer};
+
+use crate::{DateFormat, LessonChange, or_absent};
+
+/// A notification text with `{placeholder}`s filled in per change (`{{` is a literal `{`).
+///
+/// See [`Template::PLACEHOLDERS`] for the available placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(&'static str),
+}
+
+impl Template {
+    pub const PLACEHOLDERS: [&str; 14] = [
+        "title",
+        "description",
+        "kind",
+        "subject",
+        "class",
+        "group",
+        "teacher",
+        "room",
+        "old_room",
+        "status",
+        "before",
+        "after",
+        "time",
+        "date",
+    ];
+
+    /// The default title, the change's title plus its before/after values (if any).
+    #[must_use]
+    pub fn default_title() -> Self {
+        Self {
+            parts: vec![Part::Placeholder("title")],
+        }
+    }
+
+    /// The default title when watching a teacher's timetable, naming the affected classes first.
+    #[must_use]
+    pub fn teacher_title() -> Self {
+        Self {
+            parts: vec![
+                Part::Literal("Class ".to_string()),
+                Part::Placeholder("class"),
+                Part::Literal(": ".to_string()),
+                Part::Placeholder("title"),
+            ],
+        }
+    }
+
+    /// The default body, the change's description.
+    #[must_use]
+    pub fn default_body() -> Self {
+        Self {
+            parts: vec![Part::Placeholder("description")],
+        }
+    }
+
+    /// Fills in the placeholders for the change; `{date}` and `{time}` are shown in the date
+    /// and time parts of `date_format` (see [`DateFormat::format_date`]).
+    #[must_use]
+    pub fn render(&self, change: &LessonChange, date_format: &DateFormat) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Placeholder(name) => placeholder_value(name, change, date_format),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = string;
+        while let Some((before, after)) = rest.split_once('{') {
+            literal.push_str(before);
+            if let Some(after) = after.strip_prefix('{') {
+                literal.push('{');
+                rest = after;
+                continue;
+            }
+            let Some((name, after)) = after.split_once('}') else {
+                bail!("Unclosed placeholder in template {string:?}");
+            };
+            let Some(&name) = Self::PLACEHOLDERS.iter().find(|&&x| x == name) else {
+                bail!(
+                    "Unknown placeholder {{{name}}}; expected one of {:?}",
+                    Self::PLACEHOLDERS
+                );
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Placeholder(name));
+            rest = after;
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// Writes the template the way it is parsed, e.g. `{title} in {room}`.
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => f.write_str(&text.replace('{', "{{"))?,
+                Part::Placeholder(name) => write!(f, "{{{name}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes as written (see the [`Display`](fmt::Display) impl).
+impl Serialize for Template {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn placeholder_value(name: &str, change: &LessonChange, date_format: &DateFormat) -> String {
+    let lesson = change.lesson();
+    let before_after = change.before_after();
+    match name {
+        "title" => before_after.map_or_else(
+            || change.title().to_string(),
+            |(before, after)| format!("{} {before} → {after}", change.title()),
+        ),
+        "description" => change.description(),
+        "kind" => change.kind().to_string(),
+        "subject" => or_absent(lesson.subject.as_deref()).to_string(),
+        "class" => or_absent(lesson.class.as_deref()).to_string(),
+        "group" => or_absent(lesson.group.as_deref()).to_string(),
+        "teacher" => or_absent(lesson.teacher.as_deref()).to_string(),
+        "room" => or_absent(lesson.room.as_deref()).to_string(),
+        "old_room" => match change {
+            LessonChange::Room { old_room, .. } => or_absent(old_room.as_deref()).to_string(),
+            _ => or_absent(lesson.room.as_deref()).to_string(),
+        },
+        "status" => lesson.status.to_string(),
+        "before" => before_after.map(|(before, _)| before).unwrap_or_default(),
+        "after" => before_after.map(|(_, after)| after).unwrap_or_default(),
+        "time" => date_format.format_time(lesson.datetime),
+        "date" => date_format.format_date(lesson.datetime),
+        _ => unreachable!("placeholder names are checked when parsing"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_as_parsed() {
+        let source = "{{{title}}} in {room}";
+        let template: Template = source.parse().unwrap();
+        assert_eq!(template.to_string(), source);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{error, fmt};
+
+/// The error type of the crate's top-level functions, telling apart what went wrong.
+///
+/// Its `Display` only names the kind of failure; the details are in its [`source`] chain,
+/// starting with the [`Cause`]. To show everything, e.g. `Request failed: HTTP status 502`,
+/// print the chain (like `anyhow` does with `{:#}`).
+///
+/// [`source`]: error::Error::source
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A request to Untis or to a notification target failed.
+    Http(Cause),
+    /// A response could not be parsed.
+    Parse(Cause),
+    /// The API format differs from what this crate understands.
+    Format(Cause),
+    /// A lesson could not be extracted from a timetable entry.
+    Extract(Cause),
+    /// An input (e.g. a school name or token) is invalid.
+    Validation(Cause),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            Self::Http(_) => "Request failed",
+            Self::Parse(_) => "Could not parse response",
+            Self::Format(_) => "Unexpected API format",
+            Self::Extract(_) => "Could not extract lesson",
+            Self::Validation(_) => "Invalid input",
+        };
+        f.write_str(string)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Http(cause)
+            | Self::Parse(cause)
+            | Self::Format(cause)
+            | Self::Extract(cause)
+            | Self::Validation(cause) => Some(cause),
+        }
+    }
+}
+
+/// The underlying cause of an [`Error`]; its sources hold any further details.
+pub struct Cause(pub(crate) anyhow::Error);
+
+impl fmt::Debug for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl error::Error for Cause {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn alternate_anyhow_format_includes_the_cause() {
+        let err = Error::Http(Cause(anyhow!("HTTP status 502").context("Could not send")));
+        assert_eq!(err.to_string(), "Request failed");
+        assert_eq!(
+            format!("{:#}", anyhow::Error::new(err)),
+            "Request failed: Could not send: HTTP status 502"
+        );
+    }
+}
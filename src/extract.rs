@@ -1,45 +1,148 @@
 use anyhow::Result;
 
 use crate::{
-    LessonInfo,
-    untis::entries::{Day, GridEntry},
+    Cause, Error, LessonInfo,
+    discord::embed::Color,
+    untis::entries::{Day, GridEntry, Row, RowType, Status},
 };
 
-pub fn extract_all_lessons(day: &Day) -> Result<Vec<LessonInfo>> {
-    day.grid_entries
-        .iter()
-        .map(extract_lesson_info)
-        .filter_map(Result::transpose)
-        .collect::<Result<Vec<_>>>()
+impl Day {
+    /// The day's grid entries in chronological order.
+    ///
+    /// The API returns them in no particular order; entries starting at the same time keep
+    /// their original order.
+    #[must_use]
+    pub fn sorted_entries(&self) -> Vec<&GridEntry> {
+        let mut entries: Vec<&GridEntry> = self.grid_entries.iter().collect();
+        entries.sort_by_key(|entry| entry.duration.start);
+        entries
+    }
+
+    /// Lazily extracts the info of every lesson of the day in chronological order, skipping
+    /// non-lesson entries.
+    pub fn lessons(&self) -> impl Iterator<Item = Result<LessonInfo, Error>> {
+        self.sorted_entries()
+            .into_iter()
+            .map(extract_lesson_info)
+            .filter_map(Result::transpose)
+    }
+}
+
+/// Collects [`Day::lessons`], failing on the first entry that could not be extracted.
+pub fn extract_all_lessons(day: &Day) -> Result<Vec<LessonInfo>, Error> {
+    day.lessons().collect()
+}
+
+/// Like [`extract_all_lessons`], but logs and skips entries that could not be extracted,
+/// so a single odd entry doesn't prevent reporting on the rest of the day.
+pub fn extract_all_lessons_lenient(day: &Day) -> Vec<LessonInfo> {
+    day.lessons()
+        .filter_map(|lesson| {
+            lesson
+                .inspect_err(|e| log::warn!("Skipping grid entry on {}: {e:?}", day.date))
+                .ok()
+        })
+        .collect()
+}
+
+/// Extracts the info of a single lesson.
+///
+/// Entries with an info row but no subject are notes rather than lessons and are skipped
+/// (`None`). Info rows next to a lesson's subject are kept as [`LessonInfo::info`] instead.
+pub fn extract_lesson_info(lesson: &GridEntry) -> Result<Option<LessonInfo>, Error> {
+    extract(lesson).map_err(|e| Error::Extract(Cause(e)))
 }
 
-pub fn extract_lesson_info(lesson: &GridEntry) -> Result<Option<LessonInfo>> {
-    if lesson.info().is_ok() {
+fn extract(lesson: &GridEntry) -> Result<Option<LessonInfo>> {
+    if lesson.has_row(RowType::Info) && !lesson.has_row(RowType::Subject) {
         return Ok(None);
     }
 
-    let subject = lesson.subject()?;
-    let (teacher, _) = lesson.teacher_maybe_removed()?;
-    let room = lesson.room()?;
+    let subject = lesson.subject_opt()?;
+    let classes = lesson.classes();
+    // In a teacher's timetable, the classes take the place of the teacher.
+    let teacher = if classes.is_empty() {
+        lesson.teacher_maybe_removed_opt()?.map(|(row, _)| row)
+    } else {
+        None
+    };
+    let class = (!classes.is_empty()).then(|| {
+        classes
+            .iter()
+            .map(|x| x.short_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let groups: Vec<&str> = lesson
+        .student_groups()
+        .into_iter()
+        .filter_map(|row| {
+            [&row.long_name, &row.short_name]
+                .into_iter()
+                .map(|x| x.trim())
+                .find(|x| !x.is_empty())
+        })
+        .collect();
+    let group = (!groups.is_empty()).then(|| groups.join(", "));
+    let room = lesson.room_opt()?;
 
     let info = LessonInfo {
         status: lesson.status,
+        entry_type: lesson.entry_type,
         datetime: lesson.duration.start,
-        subject: subject.long_name.clone(),
-        subject_status: subject.status,
-        teacher: teacher.long_name.clone(),
-        teacher_status: teacher.status,
-        room: room.long_name.clone(),
-        room_status: room.status,
+        end: lesson.duration.end,
+        period: None,
+        web_url: None,
+        subject: subject.map(|x| x.long_name.clone()),
+        subject_status: row_status(subject),
+        subject_color: subject
+            .and_then(|x| x.back_color.as_deref())
+            .and_then(normalize_color),
+        class,
+        group,
+        teacher: teacher.map(|x| x.long_name.clone()),
+        teacher_short_name: teacher.map(|x| x.short_name.clone()),
+        teacher_status: row_status(teacher),
+        room: room.map(|x| x.long_name.clone()),
+        room_status: row_status(room),
+        room_location: room.and_then(Row::location),
+        info: lesson.info_opt().and_then(|row| {
+            normalize_str(&row.long_name).or_else(|| normalize_str(&row.short_name))
+        }),
         lesson_info: normalize_str(&lesson.lesson_info),
         lesson_text: normalize_str(&lesson.lesson_text),
         substitution_text: normalize_str(&lesson.substitution_text),
         notes: normalize_str(&lesson.notes_all),
-        texts: lesson.texts.iter().map(|x| x.text.clone()).collect(),
+        texts: lesson.texts.clone(),
+        meeting_url: find_url(
+            [
+                &lesson.lesson_text,
+                &lesson.lesson_info,
+                &lesson.substitution_text,
+                &lesson.notes_all,
+            ]
+            .into_iter()
+            .chain(lesson.texts.iter().map(|x| &x.text)),
+        ),
     };
     Ok(Some(info))
 }
 
+/// The first `http(s)://` link in the texts, without trailing punctuation.
+fn find_url<'a>(texts: impl IntoIterator<Item = &'a String>) -> Option<String> {
+    texts.into_iter().find_map(|text| {
+        let start = ["https://", "http://"]
+            .iter()
+            .filter_map(|scheme| text.find(scheme))
+            .min()?;
+        let url: &str = text[start..]
+            .split(|c: char| c.is_whitespace() || matches!(c, '"' | '<' | '>'))
+            .next()?
+            .trim_end_matches(['.', ',', ';', ':', ')', ']', '!', '?']);
+        Some(url.to_string())
+    })
+}
+
 fn normalize_str(string: &str) -> Option<String> {
     let s = string.trim();
     if s.is_empty() {
@@ -48,3 +151,55 @@ fn normalize_str(string: &str) -> Option<String> {
         Some(s.to_string())
     }
 }
+
+/// Validates a `#RRGGBB` (or `RRGGBB`) color, dropping anything else.
+fn normalize_color(hex: &str) -> Option<String> {
+    let color = Color::from_hex(hex.trim()).ok()?;
+    Some(format!("#{:06x}", color.to_discord()))
+}
+
+/// Absent rows are treated as having no data.
+fn row_status(row: Option<&Row>) -> Status {
+    row.map_or(Status::NoData, |x| x.status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::untis::entries::parse_entries;
+
+    fn grid_entry(start: &str, notes: &str) -> String {
+        format!(
+            r#"{{"duration": {{"start": "2026-10-19T{start}", "end": "2026-10-19T{start}"}},
+                "type": "NORMAL_TEACHING_PERIOD", "status": "REGULAR", "notesAll": "{notes}",
+                "position1": null, "position2": null, "position3": null, "texts": null,
+                "lessonText": null, "lessonInfo": null, "substitutionText": null}}"#
+        )
+    }
+
+    #[test]
+    fn sorted_entries_are_chronological() {
+        let entries: Vec<String> = [
+            ("11:40", "fourth"),
+            ("08:00", "first"),
+            ("09:50", "third"),
+            ("08:00", "second"),
+        ]
+        .iter()
+        .map(|(start, notes)| grid_entry(start, notes))
+        .collect();
+        let text = format!(
+            r#"{{"days": [{{"date": "2026-10-19", "status": "REGULAR", "gridEntries": [{}]}}],
+                "errors": []}}"#,
+            entries.join(",")
+        );
+        let days: Vec<Day> = parse_entries(&text).unwrap();
+
+        let notes: Vec<&str> = days[0]
+            .sorted_entries()
+            .iter()
+            .map(|entry| entry.notes_all.as_str())
+            .collect();
+        assert_eq!(notes, ["first", "second", "third", "fourth"]);
+    }
+}
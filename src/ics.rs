@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, TimeZone as _, Utc};
+use chrono_tz::Tz;
+
+use crate::LessonInfo;
+
+/// Renders lessons as an iCalendar file, e.g. for subscribing to the timetable in a calendar app.
+///
+/// Lesson times are in the school's local time; given its timezone, they are written in UTC so
+/// they show up at the right time in every timezone. Otherwise, they are written as floating
+/// local times. Cancelled lessons are kept, but marked as cancelled.
+#[must_use]
+pub fn lessons_to_ics(lessons: &[LessonInfo], timezone: Option<Tz>) -> String {
+    let stamp = format_datetime(Utc::now().naive_utc()) + "Z";
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//BioTomateDE//WebUntis//EN");
+
+    // Calendar apps update events by UID, so it should stay the same as long as the lesson does.
+    let mut uids: HashMap<String, usize> = HashMap::new();
+    for lesson in lessons {
+        let subject = lesson.subject.as_deref().unwrap_or("Lesson");
+        let start = format_local(lesson.datetime, timezone);
+        let cancelled = lesson.status.is_cancellation();
+        let summary = if cancelled {
+            format!("Cancelled: {subject}")
+        } else {
+            subject.to_string()
+        };
+
+        push_line(&mut ics, "BEGIN:VEVENT");
+        let key = lesson.key();
+        let seen: &mut usize = uids.entry(key.clone()).or_default();
+        let uid = if *seen == 0 {
+            key
+        } else {
+            format!("{key}/{seen}")
+        };
+        *seen += 1;
+        push_line(&mut ics, &format!("UID:{}@webuntis", escape(&uid)));
+        push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut ics, &format!("DTSTART:{start}"));
+        push_line(
+            &mut ics,
+            &format!("DTEND:{}", format_local(lesson.end, timezone)),
+        );
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&summary)));
+        if let Some(room) = &lesson.room {
+            push_line(&mut ics, &format!("LOCATION:{}", escape(room)));
+        }
+        let description = describe(lesson);
+        if !description.is_empty() {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape(&description)));
+        }
+        if cancelled {
+            push_line(&mut ics, "STATUS:CANCELLED");
+        }
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+fn describe(lesson: &LessonInfo) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(teacher) = &lesson.teacher {
+        lines.push(format!("Teacher: {teacher}"));
+    }
+    lines.extend(
+        lesson
+            .texts
+            .iter()
+            .map(|x| format!("{}: {}", x.text_type, x.text)),
+    );
+    if let Some(notes) = &lesson.notes {
+        lines.push(format!("Notes: {notes}"));
+    }
+    lines.join("\n")
+}
+
+/// Formats a local time as UTC if the timezone is known, or as a floating time otherwise.
+///
+/// Times skipped by a DST change can't be converted and are left floating; times that occur
+/// twice use the earlier one.
+fn format_local(datetime: NaiveDateTime, timezone: Option<Tz>) -> String {
+    timezone
+        .and_then(|tz| tz.from_local_datetime(&datetime).earliest())
+        .map_or_else(
+            || format_datetime(datetime),
+            |utc| format_datetime(utc.naive_utc()) + "Z",
+        )
+}
+
+fn format_datetime(datetime: NaiveDateTime) -> String {
+    datetime.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escapes a TEXT value (RFC 5545, section 3.3.11).
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded so no line is longer than 75 octets (RFC 5545, section 3.1);
+/// iCalendar requires CRLF line endings.
+fn push_line(ics: &mut String, line: &str) {
+    const MAX_OCTETS: usize = 75;
+    let mut rest = line;
+    let mut max = MAX_OCTETS;
+    loop {
+        let mut end = rest.len().min(max);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        ics.push_str(&rest[..end]);
+        ics.push_str("\r\n");
+        rest = &rest[end..];
+        if rest.is_empty() {
+            break;
+        }
+        // Continuation lines start with a space, which counts towards the limit.
+        ics.push(' ');
+        max = MAX_OCTETS - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta};
+
+    use super::*;
+    use crate::test_util::lesson;
+
+    fn dtstart(ics: &str) -> &str {
+        ics.lines()
+            .find_map(|line| line.strip_prefix("DTSTART:"))
+            .unwrap()
+    }
+
+    fn berlin_lesson_on(date: NaiveDate) -> String {
+        let datetime = date.and_hms_opt(8, 0, 0).unwrap();
+        let lesson = LessonInfo {
+            datetime,
+            end: datetime + TimeDelta::minutes(45),
+            ..lesson((8, 0), "Biology")
+        };
+        lessons_to_ics(&[lesson], Some(chrono_tz::Europe::Berlin))
+    }
+
+    #[test]
+    fn winter_times_are_utc_plus_one() {
+        let ics = berlin_lesson_on(NaiveDate::from_ymd_opt(2026, 1, 12).unwrap());
+        assert_eq!(dtstart(&ics), "20260112T070000Z");
+    }
+
+    #[test]
+    fn summer_times_are_utc_plus_two() {
+        let ics = berlin_lesson_on(NaiveDate::from_ymd_opt(2026, 6, 15).unwrap());
+        assert_eq!(dtstart(&ics), "20260615T060000Z");
+    }
+
+    #[test]
+    fn times_without_timezone_are_floating() {
+        let ics = lessons_to_ics(&[lesson((8, 0), "Biology")], None);
+        assert_eq!(dtstart(&ics), "20261019T080000");
+    }
+
+    #[test]
+    fn long_lines_are_folded_at_75_octets() {
+        let line = format!("DESCRIPTION:{}", "Arbeitsblatt über Größen ".repeat(10));
+        let mut ics = String::new();
+        push_line(&mut ics, &line);
+
+        let lines: Vec<&str> = ics.split_terminator("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|x| x.len() <= 75), "{lines:?}");
+        assert!(lines[1..].iter().all(|x| x.starts_with(' ')));
+        assert_eq!(ics.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn short_lines_are_kept() {
+        let mut ics = String::new();
+        push_line(&mut ics, "BEGIN:VEVENT");
+        assert_eq!(ics, "BEGIN:VEVENT\r\n");
+    }
+}
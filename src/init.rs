@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
+use reqwest::Url;
+use serde_json::json;
+use webuntis::{
+    Credentials,
+    discord::DiscordClient,
+    untis::{UntisClient, resources::Resource},
+};
+
+/// Interactively ask for everything needed to watch a timetable, testing the login and the
+/// webhook along the way, and write the result as a config file for `run --config`.
+pub fn run(path: &Path) -> Result<()> {
+    if path.exists() && !confirm(&format!("{} already exists. Overwrite?", path.display()))? {
+        bail!("Not overwriting {}", path.display());
+    }
+
+    let (credentials, untis_client) = loop {
+        let credentials = Credentials {
+            school: prompt("School (the subdomain name, e.g. \"gym-example\")")?,
+            username: prompt("WebUntis username")?,
+            password: prompt("WebUntis password")?,
+            server: None,
+        };
+        match UntisClient::login(&credentials) {
+            Ok(client) => break (credentials, client),
+            Err(e) => println!("Could not log in: {e:#}\nPlease try again.\n"),
+        }
+    };
+    println!("Login OK.\n");
+
+    let resources: Vec<Resource> = untis_client
+        .list_timetables()
+        .context("Could not list the available timetables")?;
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
+    }
+    if resources.is_empty() {
+        bail!("No timetables are visible to this account");
+    }
+    for (i, resource) in resources.iter().enumerate() {
+        println!(
+            "{:>3}) {} ({})",
+            i + 1,
+            resource.short_name,
+            resource.long_name
+        );
+    }
+    let resource: &Resource = loop {
+        let choice = prompt("Number of the timetable to watch")?;
+        match choice.parse::<usize>() {
+            Ok(n) if (1..=resources.len()).contains(&n) => break &resources[n - 1],
+            _ => println!("Please enter a number between 1 and {}.", resources.len()),
+        }
+    };
+    println!();
+
+    let webhook_url: Option<Url> = loop {
+        let url = prompt("Discord WebHook URL (leave empty to print changes to stdout)")?;
+        if url.is_empty() {
+            break None;
+        }
+        let result = DiscordClient::new(url.as_str()).and_then(|client| client.send_test());
+        match result {
+            Ok(_) => {
+                println!("Sent a test message; check your Discord channel.");
+                break Some(url.parse()?);
+            }
+            Err(e) => println!("The WebHook does not work: {e:#}\nPlease try again.\n"),
+        }
+    };
+
+    let config = json!([{
+        "school": credentials.school,
+        "username": credentials.username,
+        "password": credentials.password,
+        "timetable_id": resource.id,
+        "discord_webhook_url": webhook_url.as_ref().map(Url::as_str),
+    }]);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create directory {}", parent.display()))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&config)?)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+
+    println!(
+        "\nWrote {}. It contains your password, so keep it private.\n\
+         Start watching with: webuntis run --config {}",
+        path.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Ask a question on stdout and read the (trimmed) answer from stdin.
+fn prompt(question: &str) -> Result<String> {
+    print!("{question}: ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        bail!("Input ended before the setup was complete");
+    }
+    Ok(answer.trim().to_string())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{question} [y/N]"))?;
+    Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
@@ -0,0 +1,36 @@
+use std::{ffi::OsString, fs, io::ErrorKind, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Reads a JSON file kept between runs (e.g. the state file, named by `what` in errors), or
+/// the default value if there is none yet.
+pub fn load_json_or_default<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(e).with_context(|| format!("Could not read {what}")),
+    };
+    serde_json::from_str(&text).with_context(|| format!("Could not parse {what}"))
+}
+
+/// Writes the value as JSON (see [`write_atomically`]), creating the directory if needed.
+pub fn save_json_atomically<T: Serialize>(path: &Path, value: &T, what: &str) -> Result<()> {
+    let text = serde_json::to_string(value)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Could not create directory {}", dir.display()))?;
+    }
+    write_atomically(path, text).with_context(|| format!("Could not write {what}"))
+}
+
+/// Writes to a temporary file next to `path` first and then replaces `path` with it, so
+/// readers (or the next run, after a crash) never see a half-written file.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let mut tmp_path: OsString = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = Path::new(&tmp_path);
+    fs::write(tmp_path, contents)
+        .with_context(|| format!("Could not write {}", tmp_path.display()))?;
+    fs::rename(tmp_path, path).with_context(|| format!("Could not replace {}", path.display()))
+}
@@ -1,6 +1,8 @@
 use anyhow::anyhow;
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Deserializer};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime};
+use std::fmt::Display;
+
+use serde::{Deserialize, Deserializer, Serializer};
 
 /// Deserializes a Vec, using an empty Vec if the field is null
 pub fn parse_vec<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
@@ -19,28 +21,145 @@ where
     Ok(Option::<String>::deserialize(d)?.unwrap_or_default())
 }
 
-/// Deserializes a [`NaiveDateTime`] using the YYYY-MM-DDThh:mm format
+/// Deserializes an optional string or number as its string representation, using `None` for
+/// null and empty strings
+pub fn parse_opt_scalar<'de, D>(d: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(d)?;
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(s)) => Ok(Some(s).filter(|s| !s.trim().is_empty())),
+        Some(serde_json::Value::Number(n)) => Ok(Some(n.to_string())),
+        Some(other) => Err(serde::de::Error::custom(format!(
+            "expected a string or number, got {other}"
+        ))),
+    }
+}
+
+/// Deserializes a [`NaiveDateTime`] using the YYYY-MM-DDThh:mm format, optionally followed by a
+/// UTC offset (see [`parse_offset_datetime`])
 pub fn parse_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M").map_err(serde::de::Error::custom)
+    NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M")
+        .or_else(|e| {
+            // Report the error of the usual format if neither matches
+            parse_offset_datetime(&s)
+                .map(|dt| dt.naive_local())
+                .map_err(|_| e)
+        })
+        .map_err(serde::de::Error::custom)
+}
+
+/// Parses an offset-aware datetime like `2024-05-10T08:00+02:00` (seconds are optional).
+///
+/// Untis gives the offset of the school's timezone, so the local time of the result is the
+/// school's local time, just like the offset-less format.
+pub fn parse_offset_datetime(s: &str) -> chrono::ParseResult<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M%#z").or_else(|_| DateTime::parse_from_rfc3339(s))
+}
+
+/// Deserializes a [`NaiveTime`] using the hh:mm format
+pub fn parse_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M").map_err(serde::de::Error::custom)
+}
+
+/// Serializes an optional value as its string representation (e.g. a URL), or null
+pub fn serialize_opt_display<S, T>(value: &Option<T>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    match value {
+        Some(value) => s.collect_str(value),
+        None => s.serialize_none(),
+    }
 }
 
 pub fn improve_json_error(err: &serde_json::Error, json_string: &str) -> anyhow::Error {
-    if err.line() != 1 {
-        // Fallback if the JSON is not minified (for some reason)
+    // Only look at the offending line, in case the JSON is pretty-printed (e.g. a saved file)
+    let line = err
+        .line()
+        .checked_sub(1)
+        .and_then(|i| json_string.lines().nth(i));
+    let Some(line) = line else {
         return anyhow!("{err}");
-    }
+    };
 
     let col = err.column();
     let start = col.saturating_sub(50);
     //let start = col;
-    let end = (col + 50).min(json_string.len());
+    let end = (col + 50).min(line.len());
     let start_ell = if start == 0 { "" } else { "..." };
-    let end_ell = if end == json_string.len() { "" } else { "..." };
+    let end_ell = if end == line.len() { "" } else { "..." };
 
-    let snippet = &json_string[start..end];
+    // Column offsets may fall inside multi-byte characters
+    let Some(snippet) = line.get(start..end) else {
+        return anyhow!("{err}");
+    };
     anyhow!("{err} | {start_ell}{snippet}{end_ell}")
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn parse(s: &str) -> Result<NaiveDateTime, serde_json::Error> {
+        parse_datetime(Value::from(s))
+    }
+
+    fn at(hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 10)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_local_datetime() {
+        assert_eq!(parse("2024-05-10T08:00").unwrap(), at(8, 0, 0));
+    }
+
+    #[test]
+    fn parses_datetime_with_offset() {
+        assert_eq!(parse("2024-05-10T08:00+02:00").unwrap(), at(8, 0, 0));
+    }
+
+    #[test]
+    fn parses_datetime_with_offset_without_colon() {
+        assert_eq!(parse("2024-05-10T08:00+0200").unwrap(), at(8, 0, 0));
+    }
+
+    #[test]
+    fn parses_datetime_with_hour_only_offset() {
+        assert_eq!(parse("2024-05-10T08:00+02").unwrap(), at(8, 0, 0));
+    }
+
+    #[test]
+    fn parses_rfc3339_datetime_with_seconds() {
+        assert_eq!(parse("2024-05-10T08:00:30+02:00").unwrap(), at(8, 0, 30));
+    }
+
+    #[test]
+    fn parses_rfc3339_datetime_in_utc() {
+        assert_eq!(parse("2024-05-10T06:00:00Z").unwrap(), at(6, 0, 0));
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        for s in ["10.05.2024 08:00", "2024-05-10", "2024-05-10 08:00"] {
+            assert!(parse(s).is_err(), "{s:?}");
+        }
+    }
+}
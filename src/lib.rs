@@ -7,40 +7,145 @@
 #![allow(clippy::multiple_crate_versions)]
 
 use chrono::NaiveDateTime;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
 
-use crate::untis::entries::Status;
+use crate::untis::entries::{EntryText, EntryType, Status};
 
+mod date_format;
 mod diff;
+mod error;
 mod extract;
 mod json_util;
+mod merge;
+#[cfg(test)]
+mod test_util;
+mod tls;
 mod validate;
 
 pub mod discord;
+pub mod ics;
+pub mod notify;
+#[cfg(feature = "image")]
+pub mod timetable_image;
 pub mod untis;
+pub mod webhook;
 
-pub use diff::send_potential_diffs;
-pub use extract::{extract_all_lessons, extract_lesson_info};
+pub use date_format::DateFormat;
+pub use diff::{
+    AssignmentKeywords, LessonChange, LessonKey, TeacherMatch, diff_lessons, diff_lessons_since,
+    key_lessons, send_diffs_resumable, send_potential_diffs,
+};
+pub use error::{Cause, Error};
+pub use extract::{extract_all_lessons, extract_all_lessons_lenient, extract_lesson_info};
+pub use merge::merge_double_periods;
+pub use tls::TlsConfig;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Credentials {
     pub school: String,
     pub username: String,
     pub password: String,
+    /// Regional server host (e.g. `neilo.webuntis.com`) to use instead of the school subdomain.
+    pub server: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LessonInfo {
     pub status: Status,
+    /// Whether this is a regular lesson, an exam or an event.
+    pub entry_type: EntryType,
     pub datetime: NaiveDateTime,
-    pub subject: String,
+    pub end: NaiveDateTime,
+    /// The period number, if known (see [`untis::time_grid::TimeGrid::assign_periods`]).
+    pub period: Option<u32>,
+    /// Where to view the lesson in the WebUntis web client, if wanted.
+    #[serde(
+        serialize_with = "json_util::serialize_opt_display",
+        skip_deserializing
+    )]
+    pub web_url: Option<Url>,
+    pub subject: Option<String>,
     pub subject_status: Status,
-    pub teacher: String,
+    /// The color the school assigned to the subject, as `#RRGGBB`, if any.
+    #[serde(default)]
+    pub subject_color: Option<String>,
+    /// The attending classes (only known in a teacher's timetable).
+    pub class: Option<String>,
+    /// The student group attending (e.g. `Bio-LK`), if only part of the class is.
+    #[serde(default)]
+    pub group: Option<String>,
+    pub teacher: Option<String>,
+    pub teacher_short_name: Option<String>,
     pub teacher_status: Status,
-    pub room: String,
+    pub room: Option<String>,
     pub room_status: Status,
+    /// Building, floor and capacity of the room, if the instance provides them.
+    #[serde(default)]
+    pub room_location: Option<String>,
+    /// The content of an info row accompanying the lesson, often the class or group.
+    pub info: Option<String>,
     pub lesson_info: Option<String>,
     pub lesson_text: Option<String>,
     pub substitution_text: Option<String>,
     pub notes: Option<String>,
-    pub texts: Vec<String>,
+    pub texts: Vec<EntryText>,
+    /// The first link found in the lesson's texts, e.g. for online lessons.
+    #[serde(default)]
+    pub meeting_url: Option<String>,
+}
+
+impl LessonInfo {
+    /// The canonical identity of a single lesson, e.g. `2026-10-19T08:00/Biology`.
+    ///
+    /// Made of the date, start time and subject, so it stays the same when e.g. the room or
+    /// the teacher changes. Diffs match up lessons with [`key_lessons`] instead, which also
+    /// pairs a lesson with its substitute in another subject.
+    #[must_use]
+    pub fn key(&self) -> String {
+        format!(
+            "{}/{}",
+            self.datetime.format("%Y-%m-%dT%H:%M"),
+            self.subject.as_deref().unwrap_or("-")
+        )
+    }
+
+    /// The room, followed by its location if known, e.g. `12 (Main Building, floor 2)`.
+    #[must_use]
+    pub fn room_label(&self) -> Option<String> {
+        let room = self.room.as_ref()?;
+        Some(
+            self.room_location
+                .as_ref()
+                .map_or_else(|| room.clone(), |location| format!("{room} ({location})")),
+        )
+    }
+
+    /// The lesson on a single line, e.g. `08:00 Biology, Room 12, Mr Smith (Cancelled)`.
+    #[must_use]
+    pub fn summary_line(&self) -> String {
+        let mut line = format!(
+            "{} {}",
+            self.datetime.format("%H:%M"),
+            or_absent(self.subject.as_deref())
+        );
+        for detail in [self.room_label(), self.teacher.clone()]
+            .into_iter()
+            .flatten()
+        {
+            line.push_str(", ");
+            line.push_str(&detail);
+        }
+        if !self.status.is_normal() {
+            line.push_str(&format!(" ({})", self.status));
+        }
+        line
+    }
+}
+
+/// Placeholder shown in notifications in place of an absent subject, teacher or room.
+const ABSENT: &str = "—";
+
+fn or_absent(value: Option<&str>) -> &str {
+    value.unwrap_or(ABSENT)
 }
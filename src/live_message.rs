@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use webuntis::{LessonInfo, discord::DiscordClient};
+
+use crate::json_file::{load_json_or_default, save_json_atomically};
+
+/// A Discord message always showing the current day's timetable, edited in place whenever it
+/// changes instead of posting a new message per change.
+///
+/// The message ID is kept in a file (keyed by the watch's name), so the same message is edited
+/// after a restart; if the message was deleted, a new one is posted.
+pub struct LiveMessage {
+    client: DiscordClient,
+    path: PathBuf,
+    watch: String,
+    message_id: Option<u64>,
+    /// What the message currently shows, to leave it alone while nothing changed.
+    shown: Option<(NaiveDate, Vec<LessonInfo>)>,
+}
+
+impl LiveMessage {
+    /// The client must post as a bot, since webhooks can't edit their messages.
+    pub fn new(client: DiscordClient, path: PathBuf, watch: String) -> Self {
+        let message_id = load_file(&path)
+            .unwrap_or_else(|e| {
+                log::warn!("[{watch}] Could not load the live message ID: {e:?}");
+                HashMap::new()
+            })
+            .remove(&watch);
+        Self {
+            client,
+            path,
+            watch,
+            message_id,
+            shown: None,
+        }
+    }
+
+    /// Show the day's lessons in the message, posting it first if there is none yet.
+    pub fn update(&mut self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<()> {
+        if self
+            .shown
+            .as_ref()
+            .is_some_and(|(d, l)| *d == date && l == lessons)
+        {
+            return Ok(());
+        }
+        let edited: bool = match self.message_id {
+            Some(id) => self.client.edit_timetable_message(id, date, lessons)?,
+            None => false,
+        };
+        if !edited {
+            log::info!("[{}] Posting a new live timetable message.", self.watch);
+            let id: u64 = self.client.post_timetable_message(date, lessons)?;
+            self.message_id = Some(id);
+            self.save(id)?;
+        }
+        self.shown = Some((date, lessons.to_vec()));
+        Ok(())
+    }
+
+    fn save(&self, message_id: u64) -> Result<()> {
+        let mut file = load_file(&self.path).unwrap_or_else(|e| {
+            log::warn!("Overwriting unreadable live message file: {e:?}");
+            HashMap::new()
+        });
+        file.insert(self.watch.clone(), message_id);
+        save_json_atomically(&self.path, &file, "live message file")
+    }
+}
+
+fn load_file(path: &Path) -> Result<HashMap<String, u64>> {
+    load_json_or_default(path, "live message file")
+}
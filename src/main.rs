@@ -1,26 +1,129 @@
+mod app;
+mod bench;
+mod config;
+mod init;
+mod json_file;
+mod live_message;
 mod logging;
+mod paths;
+mod resource_cache;
+mod state_store;
+mod status_server;
 
 use std::{
-    iter::zip,
+    collections::BTreeMap,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
     thread::sleep,
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, bail};
-use chrono::{DateTime, Days, NaiveDate, NaiveTime, Timelike, Utc};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Days, NaiveDate, NaiveTime, Utc, Weekday};
 use chrono_tz::Tz;
-use clap::Parser;
+use clap::{ArgAction, Args, Parser, Subcommand};
 use reqwest::Url;
+use serde::Serialize;
+#[cfg(feature = "image")]
+use webuntis::timetable_image::TimetableRenderer;
 use webuntis::{
-    Credentials, LessonInfo,
-    discord::DiscordClient,
-    extract_all_lessons, send_potential_diffs,
-    untis::{UntisClient, entries::Day},
+    AssignmentKeywords, Credentials, DateFormat, LessonChange, LessonInfo, TeacherMatch, TlsConfig,
+    diff_lessons,
+    discord::{
+        DiscordClient, Forum, Identities, Identity, LessonField, NotifyStyle, Template,
+        WebhookCredentials,
+    },
+    extract_all_lessons, extract_all_lessons_lenient, extract_lesson_info,
+    ics::lessons_to_ics,
+    notify::{Notifier, RecentNotifications, StdoutNotifier},
+    untis::{
+        HostAllowlist, UntisClient,
+        entries::{Day, ResourceType, Status, Timetable, parse_entries},
+    },
+};
+
+use crate::{
+    app::{App, Baseline, StartupMode, Watch, WatchOptions, WatchRange},
+    config::WatchConfig,
+    resource_cache::ResourceCache,
+    state_store::StateStore,
 };
 
 /// WebUntis Notification Bot
 #[derive(Parser)]
-struct Args {
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the notification bot
+    #[command(visible_alias = "watch")]
+    Run(Box<RunArgs>),
+
+    /// Check that a Discord WebHook URL is valid by sending a single test message
+    TestWebhook {
+        /// The Discord WebHook URL to test
+        #[arg(short, long)]
+        discord_webhook_url: Url,
+    },
+
+    /// Print how a day's timetable differs from the same weekday some weeks earlier
+    Compare(CompareArgs),
+
+    /// Print the changes between the timetables of any two days as JSON lines, to check the
+    /// change detection against live data on demand
+    ReplayDates(ReplayDatesArgs),
+
+    /// Periodically export the upcoming timetable to an ICS file, for calendar subscriptions
+    ServeIcs(ServeIcsArgs),
+
+    /// Set up a config file interactively, testing the login and the webhook
+    Init {
+        /// Where to write the config file
+        #[arg(long, default_value = "webuntis.json")]
+        config: PathBuf,
+    },
+
+    /// Check for changes once, for scripts: prints every change since the last check as a JSON
+    /// line and exits with code 2 if there were any (0 if not)
+    ListChanges(ListChangesArgs),
+
+    /// Save a week of the timetable as the reference for `run --reference-file`
+    ExportReference(ExportReferenceArgs),
+
+    /// Log in and fetch today once, for uptime checks: prints the result of every stage as JSON
+    /// and exits with a nonzero code if any of them failed
+    Probe(LoginArgs),
+
+    /// List the IDs and names of the class or room timetables the account can view, e.g. to
+    /// find the Timetable ID of a room to watch
+    ListResources(ListResourcesArgs),
+
+    /// Show how a saved `timetable/entries` JSON response is understood, for bug reports
+    ParseFile {
+        /// The JSON file to parse
+        path: PathBuf,
+    },
+
+    /// Time parsing, extracting and diffing of a saved `timetable/entries` JSON response
+    Bench {
+        /// The JSON file to use instead of fetching
+        path: PathBuf,
+
+        /// How often to run every stage
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+        iterations: u64,
+    },
+}
+
+/// Credentials for the one-off subcommands.
+#[derive(Args)]
+struct CredentialsArgs {
     /// Subdomain Name of the school
     #[arg(short, long)]
     school: String,
@@ -33,181 +136,1054 @@ struct Args {
     #[arg(short, long)]
     password: String,
 
+    /// Regional server host (e.g. `neilo.webuntis.com`) to log in through instead of the
+    /// school subdomain
+    #[arg(long)]
+    server: Option<String>,
+}
+
+/// Credentials and timetable for the one-off subcommands.
+#[derive(Args)]
+struct LoginArgs {
+    #[command(flatten)]
+    credentials: CredentialsArgs,
+
     /// The Timetable ID (aka `resources` in json)
+    #[arg(
+        short,
+        long,
+        required_unless_present = "class",
+        conflicts_with = "class"
+    )]
+    timetable_id: Option<i32>,
+
+    /// The class name (short or long, case-insensitive) to resolve the Timetable ID from
+    #[arg(short, long, visible_alias = "timetable-name")]
+    class: Option<String>,
+
+    /// What kind of timetable the Timetable ID refers to (class, teacher, room or student)
+    #[arg(long, default_value = "class", conflicts_with = "class")]
+    resource_type: ResourceType,
+}
+
+#[derive(Args)]
+struct ListResourcesArgs {
+    #[command(flatten)]
+    credentials: CredentialsArgs,
+
+    /// What kind of timetables to list (class or room)
+    #[arg(long, default_value = "class")]
+    resource_type: ResourceType,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// The day to compare (YYYY-MM-DD)
+    #[arg(long)]
+    date: NaiveDate,
+
+    /// How many weeks earlier the day to compare against is
+    #[arg(long, default_value_t = 1)]
+    against_weeks_ago: u64,
+
+    /// Which of the teacher's names decides whether the teacher changed
+    /// (long-name or short-name)
+    #[arg(long, default_value = "long-name")]
+    teacher_match_by: TeacherMatch,
+}
+
+#[derive(Args)]
+struct ReplayDatesArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// The day taken as the old timetable (YYYY-MM-DD)
+    #[arg(long)]
+    date_a: NaiveDate,
+
+    /// The day taken as the new timetable (YYYY-MM-DD)
+    #[arg(long)]
+    date_b: NaiveDate,
+
+    /// Which of the teacher's names decides whether the teacher changed
+    /// (long-name or short-name)
+    #[arg(long, default_value = "long-name")]
+    teacher_match_by: TeacherMatch,
+}
+
+#[derive(Args)]
+struct ListChangesArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// File remembering the lessons seen by the last check (created on the first run)
+    #[arg(long)]
+    state: PathBuf,
+
+    /// How many days (starting today) to check
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..=31))]
+    days: u64,
+
+    /// Which of the teacher's names decides whether the teacher changed
+    /// (long-name or short-name)
+    #[arg(long, default_value = "long-name")]
+    teacher_match_by: TeacherMatch,
+}
+
+#[derive(Args)]
+struct ExportReferenceArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// Any day of the week to save (YYYY-MM-DD)
+    #[arg(long)]
+    week_of: NaiveDate,
+
+    /// The JSON file to write
     #[arg(short, long)]
-    timetable_id: i32,
+    output: PathBuf,
+}
 
-    /// The Discord WebHook URL the notifications should be sent to
+#[derive(Args)]
+struct ServeIcsArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// The ICS file to (re)write
     #[arg(short, long)]
-    discord_webhook_url: Url,
+    output: PathBuf,
+
+    /// Minutes between refreshes
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    refresh_mins: u64,
+
+    /// How many days (starting today) to export
+    #[arg(long, default_value_t = 14, value_parser = clap::value_parser!(u64).range(1..=62))]
+    days: u64,
+
+    /// The school's timezone, to write lesson times in UTC
+    /// [default: the school's configured timezone, or floating local times]
+    #[arg(short = 'z', long)]
+    timezone: Option<Tz>,
+}
+
+/// Watch-level arguments and secrets are not serialized; they are part of [`WatchConfig`].
+#[derive(Args, Serialize)]
+struct RunArgs {
+    /// JSON file with a list of watches (school accounts and timetables), to watch multiple
+    /// timetables at once instead of the single one given on the command line
+    #[arg(long, conflicts_with_all = ["school", "username", "password", "server", "timetable_id", "class", "discord_webhook_url", "discord_bot_token", "webhook_url", "webhook_header", "timezone"])]
+    config: Option<PathBuf>,
+
+    /// Subdomain Name of the school
+    #[arg(short, long, required_unless_present = "config")]
+    #[serde(skip)]
+    school: Option<String>,
+
+    /// Your WebUntis username
+    #[arg(short, long, required_unless_present = "config")]
+    #[serde(skip)]
+    username: Option<String>,
+
+    /// Your WebUntis password
+    #[arg(short, long, required_unless_present = "config")]
+    #[serde(skip)]
+    password: Option<String>,
+
+    /// Regional server host (e.g. `neilo.webuntis.com`) to log in through instead of the
+    /// school subdomain
+    #[arg(long)]
+    #[serde(skip)]
+    server: Option<String>,
+
+    /// The Timetable ID (aka `resources` in json)
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["class", "config"],
+        conflicts_with = "class"
+    )]
+    #[serde(skip)]
+    timetable_id: Option<i32>,
+
+    /// The class name (short or long, case-insensitive) to resolve the Timetable ID from
+    #[arg(short, long, visible_alias = "timetable-name")]
+    #[serde(skip)]
+    class: Option<String>,
+
+    /// What kind of timetable the Timetable ID refers to (class, teacher, room or student);
+    /// teacher and room timetables get notifications naming the affected classes. A room
+    /// timetable reports every lesson booked into, moved out of or cancelled in the room; find
+    /// its ID with `list-resources --resource-type room`
+    #[arg(long, default_value = "class")]
+    resource_type: ResourceType,
+
+    /// The Discord WebHook URL the notifications should be sent to
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["config", "stdout", "webhook_url", "discord_webhook_url_file", "discord_bot_token"]
+    )]
+    #[serde(skip)]
+    discord_webhook_url: Option<Url>,
+
+    /// File containing the Discord WebHook URL (e.g. a Docker secret), to keep it off the
+    /// command line
+    #[arg(
+        long,
+        env = "DISCORD_WEBHOOK_URL_FILE",
+        conflicts_with_all = ["config", "discord_webhook_url"]
+    )]
+    #[serde(skip)]
+    discord_webhook_url_file: Option<PathBuf>,
+
+    /// Post to a Discord channel as this bot instead of through a webhook (the token from the
+    /// developer portal, without the `Bot ` prefix); the bot needs permission to send messages
+    #[arg(
+        long,
+        env = "DISCORD_BOT_TOKEN",
+        requires = "discord_channel_id",
+        conflicts_with_all = ["discord_webhook_url", "discord_webhook_url_file", "forum"]
+    )]
+    #[serde(skip)]
+    discord_bot_token: Option<String>,
+
+    /// The ID of the Discord channel the bot posts to
+    #[arg(long, requires = "discord_bot_token")]
+    #[serde(skip)]
+    discord_channel_id: Option<u64>,
+
+    /// Send notifications as JSON to this generic webhook instead of Discord
+    #[arg(long, conflicts_with_all = ["discord_webhook_url", "discord_webhook_url_file", "discord_bot_token", "forum"])]
+    #[serde(skip)]
+    webhook_url: Option<Url>,
+
+    /// Extra header for the generic webhook, as `<name>: <value>` (can be repeated),
+    /// e.g. `Authorization: Bearer <token>`
+    #[arg(long, value_parser = parse_header, requires = "webhook_url")]
+    #[serde(skip)]
+    webhook_header: Vec<(String, String)>,
+
+    /// Discord WebHook URL to send notifications and errors to whenever sending them the usual
+    /// way failed, e.g. a second channel for when the first one is down
+    #[arg(long, conflicts_with = "stdout")]
+    #[serde(serialize_with = "config::serialize_opt_redacted_url")]
+    fallback_webhook_url: Option<Url>,
+
+    /// Print changes to stdout instead of sending them to Discord
+    #[arg(long, conflicts_with_all = ["config", "discord_webhook_url", "discord_webhook_url_file", "discord_bot_token", "webhook_url", "forum"])]
+    stdout: bool,
+
+    /// Post every notification as a new thread (the webhook must target a forum channel)
+    #[arg(long)]
+    forum: bool,
+
+    /// Forum tag to apply per change kind, as `<kind>=<tag id>` (can be repeated).
+    /// Kinds: cancellation, change, reinstated, entry_type, subject, teacher, room, time,
+    /// substitution, notes, assignment, meeting_link, reverted, added, removed
+    #[arg(long, value_parser = parse_forum_tag, requires = "forum")]
+    forum_tag: Vec<(String, u64)>,
+
+    /// Post notifications about changes of a kind (see `--forum-tag`) with another username
+    /// and avatar, as `<kind>=<username>[|<avatar URL>]` (can be repeated), e.g.
+    /// `cancellation=Cancelled|https://example.com/red.png`
+    #[arg(long, value_parser = parse_identity)]
+    identity: Vec<(String, Identity)>,
+
+    /// Which standard lesson fields to show in notifications, comma-separated
+    /// (fields without a value are always left out)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "subject,class,teacher,room,time"
+    )]
+    embed_fields: Vec<LessonField>,
+
+    /// How to render Discord change notifications: `detailed` embeds with the lesson's
+    /// fields, or `compact` single lines like `❌ 08:00 Math (Room 12) — cancelled`
+    #[arg(long, default_value = "detailed")]
+    notify_style: NotifyStyle,
+
+    /// Prefix the title of every Discord message (including errors) with this label in
+    /// brackets, e.g. `10B`, to tell apart several bots posting to the same channel
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Seconds to sleep between checks outside of school hours (at most 900, so the bot
+    /// doesn't stay unresponsive for too long)
+    #[arg(
+        long,
+        default_value_t = 200,
+        value_parser = clap::value_parser!(u64).range(1..=900)
+    )]
+    max_lookahead_sleep: u64,
+
+    /// How to show lesson dates in notifications, as a chrono strftime pattern (its date and time
+    /// parts are also used for the `{date}` and `{time}` template placeholders)
+    #[arg(long, default_value_t = DateFormat::default())]
+    date_format: DateFormat,
+
+    /// Template for the title of Discord change notifications, with placeholders like
+    /// `{subject}`, `{room}`, `{old_room}`, `{status}`, `{time}` or `{date}` [default: {title}]
+    #[arg(long)]
+    title_template: Option<Template>,
+
+    /// Template for the description of Discord change notifications (see --title-template)
+    /// [default: {description}]
+    #[arg(long)]
+    body_template: Option<Template>,
+
+    /// First day of a fixed range of dates to watch, instead of only the current day
+    /// (YYYY-MM-DD)
+    #[arg(long, requires = "watch_end")]
+    watch_start: Option<NaiveDate>,
+
+    /// Last day (inclusive) of the fixed range of dates to watch (YYYY-MM-DD)
+    #[arg(long, requires = "watch_start")]
+    watch_end: Option<NaiveDate>,
+
+    /// Also watch this many days after the current day
+    #[arg(
+        long,
+        conflicts_with = "watch_start",
+        value_parser = clap::value_parser!(u64).range(1..=30)
+    )]
+    watch_days_ahead: Option<u64>,
+
+    /// Skip (and log) single grid entries that could not be understood, instead of failing
+    /// the whole day
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    lenient_extract: bool,
+
+    /// Link every notification to the timetable in the WebUntis web client
+    #[arg(long)]
+    deep_link: bool,
+
+    /// Don't notify about changes to lessons that are already over
+    #[arg(long)]
+    skip_past_lessons: bool,
+
+    /// Which of the teacher's names decides whether the teacher changed (long-name or
+    /// short-name, for schools that keep tweaking the full names)
+    #[arg(long, default_value = "long-name")]
+    teacher_match_by: TeacherMatch,
+
+    /// Words marking a new note or substitution text as setting work (comma-separated,
+    /// case-insensitive), reported as "Assignment Set" instead of a notes change; an empty
+    /// value turns this off [default: German and English words like `Arbeitsblatt` and `worksheet`]
+    #[arg(long, value_delimiter = ',')]
+    assignment_keyword: Option<Vec<String>>,
+
+    /// Day statuses that mean there is no school that day (comma-separated, e.g.
+    /// `no-data,not-allowed`); such days are not checked for changes
+    #[arg(long, value_delimiter = ',')]
+    no_school_status: Vec<Status>,
+
+    /// Only check these weekdays for changes (comma-separated, e.g. `Mon,Wed,Fri`), for
+    /// people who don't attend every day [default: all]
+    #[arg(long, value_delimiter = ',')]
+    notify_weekdays: Vec<Weekday>,
+
+    /// Report identical changes to back-to-back lessons that are otherwise identical as one,
+    /// so e.g. a cancelled double period is only reported once
+    #[arg(long)]
+    merge_double_periods: bool,
+
+    /// Also report exams that are added, changed or removed in the next four weeks (class
+    /// timetables only)
+    #[arg(long)]
+    notify_exams: bool,
+
+    /// Also post new school-wide announcements (messages of the day), e.g. about a fire drill
+    #[arg(long)]
+    notify_messages: bool,
+
+    /// Once a day, look this many days ahead and report when days without lessons get a
+    /// timetable, e.g. when next week's timetable is published
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    publish_lookahead_days: Option<u64>,
+
+    /// Serve the most recent notifications as JSON at `http://<ADDR>/recent` (e.g.
+    /// `127.0.0.1:8080`), to check whether the bot notified about something
+    #[arg(long)]
+    status_addr: Option<SocketAddr>,
+
+    /// How many notifications `/recent` keeps (see --status-addr)
+    #[arg(long, default_value_t = 50)]
+    recent_size: usize,
+
+    /// Buffer detected changes for this many seconds and only send the net change once the
+    /// timetable has settled (0 = send immediately)
+    #[arg(long, default_value_t = 0)]
+    coalesce_secs: u64,
+
+    /// Instead of notifying about every change, send one digest per day at this time (HH:MM,
+    /// school time) comparing the day against the first timetable seen for it
+    #[arg(long, conflicts_with_all = ["baseline", "coalesce_secs"])]
+    digest_at: Option<NaiveTime>,
+
+    /// Don't send any changes while this file exists (e.g. to mute the bot during holidays);
+    /// changes made in the meantime are not sent after resuming either
+    #[arg(long)]
+    pause_file: Option<PathBuf>,
+
+    /// Give up on a Discord webhook request after this many seconds
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    webhook_timeout_secs: u64,
+
+    /// Send an error identical to the previous one at most once per this many minutes
+    /// (0 = send every error)
+    #[arg(long, default_value_t = 15)]
+    error_repeat_mins: u64,
+
+    /// Abort an iteration that is still retrying after this many seconds, so it can't block the
+    /// next poll (0 = no limit)
+    #[arg(long, default_value_t = 0)]
+    iteration_timeout_secs: u64,
+
+    /// Log in again after this many minutes, before the session expires; lower it if the
+    /// school's sessions expire sooner
+    #[arg(long, default_value_t = 14, value_parser = clap::value_parser!(u64).range(1..))]
+    relogin_mins: u64,
+
+    /// What to compare the timetable against: `rolling` reports every change relative to the
+    /// previous fetch, `sticky` reports the net change relative to the first timetable seen
+    /// that day (so changes that are undone again produce a "reverted" notice)
+    #[arg(long, value_enum, default_value_t = Baseline::Rolling)]
+    baseline: Baseline,
+
+    /// A week of lessons written by `export-reference` (e.g. the printed schedule) to report
+    /// every deviation from, instead of changes between fetches; undone deviations are
+    /// reported as reverted
+    #[arg(long, conflicts_with_all = ["config", "baseline"])]
+    #[serde(skip)]
+    reference_file: Option<PathBuf>,
+
+    /// File caching the list of classes per account, to resolve class names without a request
+    /// [default: `resources.json` in the user's cache directory]
+    #[arg(long)]
+    resources_cache: Option<PathBuf>,
+
+    /// What to do with the first timetable fetched for each day: `silent` takes it as the
+    /// baseline, `announce` sends it once, `diff` reports how it differs from the timetable
+    /// seen before the bot was last stopped
+    #[arg(long, value_enum, default_value_t = StartupMode::Silent)]
+    on_startup: StartupMode,
+
+    /// Attach an image of the timetable, drawn with this TrueType font, to the Discord messages
+    /// sent by `--on-startup announce`
+    #[cfg(feature = "image")]
+    #[arg(long)]
+    timetable_image_font: Option<PathBuf>,
+
+    /// Keep a single message showing the relevant day's timetable up to date, editing it
+    /// whenever the timetable changes (needs `--discord-bot-token`)
+    #[arg(long)]
+    live_message: bool,
+
+    /// File keeping the ID of the live message between runs, so it is edited after a restart
+    /// [default: `live_messages.json` in the user's state directory]
+    #[arg(long, requires = "live_message")]
+    live_message_file: Option<PathBuf>,
+
+    /// File keeping the last seen lessons between runs, for `--on-startup diff`
+    /// [default: `state.json` in the user's state directory]
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+
+    /// Refetch the list of classes even if the cached one is still fresh
+    #[arg(long)]
+    refresh_resources: bool,
+
+    /// Additional PEM root certificate to trust, e.g. for networks intercepting TLS
+    #[arg(long)]
+    ca_cert: Option<PathBuf>,
+
+    /// Accept invalid TLS certificates. DANGEROUS: this allows anyone to intercept your
+    /// credentials; only use as a last resort for broken network setups
+    #[arg(long)]
+    danger_accept_invalid_certs: bool,
+
+    /// Host suffixes the Untis server may have (comma-separated); logging in to any other
+    /// host fails before the credentials are sent
+    #[arg(long, value_delimiter = ',', default_value = HostAllowlist::DEFAULT_SUFFIX)]
+    allowed_host_suffix: Vec<String>,
 
     /// The timezone to consider for the dates returned by the Untis API
-    #[arg(short = 'z', long, default_value_t = Tz::UTC)]
-    timezone: Tz,
-}
-
-/// Allow for some buffer time
-const MAX_LOGIN_TIME: Duration = Duration::from_mins(14);
-
-struct App {
-    discord_client: DiscordClient,
-    untis_client: UntisClient,
-    credentials: Credentials,
-    last_login: Instant,
-    timetable_id: i32,
-    timezone: Tz,
-    prev_date: NaiveDate,
-    prev_lessons: Option<Vec<LessonInfo>>,
-}
-
-impl App {
-    #[must_use]
-    fn new(
-        discord_client: DiscordClient,
-        untis_client: UntisClient,
-        timetable_id: i32,
-        timezone: Tz,
-        credentials: Credentials,
-    ) -> Self {
-        Self {
-            discord_client,
-            untis_client,
-            timetable_id,
-            last_login: Instant::now(),
-            prev_date: NaiveDate::default(),
-            prev_lessons: None,
-            credentials,
-            timezone,
-        }
+    /// [default: the school's configured timezone, or UTC]
+    #[arg(short = 'z', long)]
+    #[serde(skip)]
+    timezone: Option<Tz>,
+
+    /// Print the effective configuration (all watches and settings) as JSON with secrets
+    /// redacted, then exit without watching
+    #[arg(long)]
+    #[serde(skip)]
+    dump_config: bool,
+}
+
+/// Exit code of `list-changes` if there were changes.
+const EXIT_CHANGES: u8 = 2;
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    logging::init();
+
+    let result = match cli.command {
+        Command::Run(args) => run(*args),
+        Command::TestWebhook {
+            discord_webhook_url,
+        } => test_webhook(discord_webhook_url),
+        Command::Compare(args) => compare(&args),
+        Command::ReplayDates(args) => replay_dates(&args),
+        Command::ServeIcs(args) => serve_ics(&args),
+        Command::Init { config } => init::run(&config),
+        Command::ParseFile { path } => parse_file(&path),
+        Command::Bench { path, iterations } => bench::run(&path, iterations),
+        Command::ListChanges(args) => return list_changes(&args),
+        Command::ExportReference(args) => export_reference(&args),
+        Command::ListResources(args) => list_resources(&args),
+        Command::Probe(args) => return Ok(probe(&args)),
+    };
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+fn list_changes(args: &ListChangesArgs) -> Result<ExitCode> {
+    let (untis_client, timetable) = args.login.login()?;
+    let start: NaiveDate = school_today(&untis_client);
+    let end: NaiveDate = start
+        .checked_add_days(Days::new(args.days - 1))
+        .context("Date range is out of range")?;
+    let days = untis_client.fetch_entries(start, end, timetable);
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
     }
+    let lessons: BTreeMap<NaiveDate, Vec<LessonInfo>> = days?
+        .iter()
+        .map(|day| Ok((day.date, extract_all_lessons(day)?)))
+        .collect::<Result<_>>()?;
 
-    fn ensure_login_validity(&mut self) -> Result<()> {
-        let now = Instant::now();
-        if now - self.last_login < MAX_LOGIN_TIME {
-            return Ok(());
+    let previous: BTreeMap<NaiveDate, Vec<LessonInfo>> = match fs::read_to_string(&args.state) {
+        Ok(text) => serde_json::from_str(&text)
+            .with_context(|| format!("Invalid state file {}", args.state.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            log::info!("No previous state; only remembering the current lessons.");
+            BTreeMap::new()
         }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Could not read {}", args.state.display()));
+        }
+    };
 
-        log::info!("Max login time {MAX_LOGIN_TIME:?} expired; creating new session.");
-        self.last_login = now;
-        self.untis_client =
-            UntisClient::login(&self.credentials).context("Could not log back into Untis")?;
-        Ok(())
-    }
-
-    fn iteration(&mut self) -> Result<()> {
-        log::debug!("Iteration");
-        self.ensure_login_validity()?;
-
-        let now: DateTime<Utc> = Utc::now();
-        let date: NaiveDate = get_relevant_date(now.with_timezone(&self.timezone));
-        let day: Day = self
-            .untis_client
-            .fetch_single_entry(date, self.timetable_id)?;
-        let lessons: Vec<LessonInfo> = extract_all_lessons(&day)?;
-        drop(day);
-
-        let Some(prev_lessons) = &self.prev_lessons else {
-            self.prev_lessons = Some(lessons);
-            self.prev_date = date;
-            return Ok(());
+    let mut changed = false;
+    for (date, new) in &lessons {
+        let Some(old) = previous.get(date) else {
+            continue;
         };
-
-        // If it's a different day now, invalidate the "previous day" and rerun.
-        if self.prev_date != date {
-            self.prev_lessons = None;
-            log::info!("Another day, another victory for the OGs.");
-            return Ok(());
+        for change in diff_lessons(old, new, args.teacher_match_by) {
+            println!("{}", serde_json::to_string(&change)?);
+            changed = true;
         }
+    }
 
-        if prev_lessons.len() != lessons.len() {
-            bail!(
-                "Previous and current 'day' have a different number of lessons: {} vs  {}",
-                prev_lessons.len(),
-                lessons.len(),
-            );
-        }
+    fs::write(&args.state, serde_json::to_string(&lessons)?)
+        .with_context(|| format!("Could not write {}", args.state.display()))?;
 
-        let mut needs_reset: bool = false;
-        for (old_lesson, new_lesson) in zip(prev_lessons, &lessons) {
-            let sent: bool = send_potential_diffs(&self.discord_client, old_lesson, new_lesson)?;
-            if sent {
-                needs_reset = true;
+    Ok(if changed {
+        ExitCode::from(EXIT_CHANGES)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn probe(args: &LoginArgs) -> ExitCode {
+    let start = Instant::now();
+    let (login, fetch, format_version) = match args.login() {
+        Ok((untis_client, timetable)) => {
+            let today: NaiveDate = school_today(&untis_client);
+            let fetch = untis_client.fetch_entries(today, today, timetable);
+            let format_version = untis_client.format_version();
+            if let Err(e) = untis_client.logout() {
+                log::warn!("Could not log out of Untis: {e:?}");
             }
+            (Ok(()), fetch.map(drop), format_version)
         }
+        Err(e) => (Err(e), Err(anyhow!("skipped")), None),
+    };
+    let latency_ms = start.elapsed().as_millis();
+
+    let stage = |result: &Result<()>| match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("{e:#}"),
+    };
+    let report = serde_json::json!({
+        "login": stage(&login),
+        "fetch": stage(&fetch),
+        "latency_ms": latency_ms,
+        "format_version": format_version,
+    });
+    println!("{report}");
+
+    if login.is_ok() && fetch.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn list_resources(args: &ListResourcesArgs) -> Result<()> {
+    let untis_client = args.credentials.login()?;
+    let resources = untis_client.list_resources(args.resource_type);
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
+    }
 
-        // If there was a change, invalidate the "previous day".
-        if needs_reset {
-            self.prev_lessons = None;
+    for resource in resources? {
+        println!(
+            "{}\t{}\t{}",
+            resource.id, resource.short_name, resource.long_name
+        );
+    }
+    Ok(())
+}
+
+fn parse_file(path: &Path) -> Result<()> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let days: Vec<Day> = parse_entries(&text)?;
+
+    for day in &days {
+        println!(
+            "{} ({}, {} entries)",
+            day.date,
+            day.status,
+            day.grid_entries.len()
+        );
+        for entry in &day.grid_entries {
+            let time = format!(
+                "{}–{}",
+                entry.duration.start.format("%H:%M"),
+                entry.duration.end.format("%H:%M")
+            );
+            let verdict = match extract_lesson_info(entry) {
+                Ok(Some(lesson)) => format!(
+                    "kept: {} / {} / {}",
+                    lesson.subject.as_deref().unwrap_or("no subject"),
+                    lesson.teacher.as_deref().unwrap_or("no teacher"),
+                    lesson.room.as_deref().unwrap_or("no room"),
+                ),
+                Ok(None) => "skipped: info entry, not a lesson".to_string(),
+                Err(e) => format!("error: {:#}", anyhow::Error::from(e)),
+            };
+            println!(
+                "  {time} {:?} ({}): {verdict}",
+                entry.entry_type, entry.status
+            );
         }
+    }
+    Ok(())
+}
+
+fn test_webhook(url: Url) -> Result<()> {
+    let discord_client = DiscordClient::new(url).context("Invalid Discord WebHook URL")?;
+    let status = discord_client
+        .send_test()
+        .context("Could not send test message")?;
+    log::info!("Webhook test succeeded with status {status}.");
+    Ok(())
+}
 
-        let dur = get_sleep_time(now);
-        sleep(dur);
-        Ok(())
+impl CredentialsArgs {
+    fn login(&self) -> Result<UntisClient> {
+        let credentials = Credentials {
+            school: self.school.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+            server: self.server.clone(),
+        };
+        UntisClient::login(&credentials).context("Could not log into Untis")
     }
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    logging::init();
+impl LoginArgs {
+    /// Log in and resolve the timetable.
+    fn login(&self) -> Result<(UntisClient, Timetable)> {
+        let untis_client = self.credentials.login()?;
+        let timetable = match (self.timetable_id, &self.class) {
+            (Some(id), _) => Timetable {
+                kind: self.resource_type,
+                id,
+            },
+            (None, Some(class)) => untis_client
+                .resolve_timetable(class)
+                .map(Timetable::class)
+                .context("Could not resolve class name")?,
+            (None, None) => bail!("Missing --timetable-id or --class"),
+        };
+        Ok((untis_client, timetable))
+    }
+}
+
+/// Today in the school's timezone (or UTC if it can't be detected), since Untis dates are local.
+fn school_today(untis_client: &UntisClient) -> NaiveDate {
+    let timezone: Tz = untis_client.fetch_school_timezone().unwrap_or_else(|e| {
+        log::warn!("Could not detect school timezone; taking today in UTC: {e:?}");
+        Tz::UTC
+    });
+    Utc::now().with_timezone(&timezone).date_naive()
+}
 
-    let discord_client = DiscordClient::new(args.discord_webhook_url)
-        .context("Could not create Discord Webhook Client")?;
+fn compare(args: &CompareArgs) -> Result<()> {
+    let earlier_date: NaiveDate = args
+        .date
+        .checked_sub_days(Days::new(7 * args.against_weeks_ago))
+        .context("Date to compare against is out of range")?;
+    let changes = diff_dates(&args.login, earlier_date, args.date, args.teacher_match_by)?;
+    if changes.is_empty() {
+        log::info!("No differences between {earlier_date} and {}.", args.date);
+    }
+    let notifier = StdoutNotifier::new(format!("{earlier_date} → {}", args.date));
+    for change in &changes {
+        notifier.send_change(change)?;
+    }
+    Ok(())
+}
 
-    log::info!("Logging into Untis...");
-    let credentials = Credentials {
-        school: args.school,
-        username: args.username,
-        password: args.password,
+fn replay_dates(args: &ReplayDatesArgs) -> Result<()> {
+    let changes = diff_dates(&args.login, args.date_a, args.date_b, args.teacher_match_by)?;
+    if changes.is_empty() {
+        log::info!(
+            "No differences between {} and {}.",
+            args.date_a,
+            args.date_b
+        );
+    }
+    for change in &changes {
+        println!("{}", serde_json::to_string(change)?);
+    }
+    Ok(())
+}
+
+/// Fetch both days and diff the lessons, taking `old_date`'s as the old ones.
+fn diff_dates(
+    login: &LoginArgs,
+    old_date: NaiveDate,
+    new_date: NaiveDate,
+    teacher_match: TeacherMatch,
+) -> Result<Vec<LessonChange>> {
+    let (untis_client, timetable) = login.login()?;
+    let fetch = |date: NaiveDate| -> Result<Vec<LessonInfo>> {
+        let day = untis_client.fetch_single_entry(date, timetable)?;
+        Ok(extract_all_lessons(&day)?)
     };
-    let untis_client = UntisClient::login(&credentials).context("Could not log into Untis")?;
-
-    let mut app = App::new(
-        discord_client,
-        untis_client,
-        args.timetable_id,
-        args.timezone,
-        credentials,
-    );
+    let lessons = fetch(old_date).and_then(|old| Ok((old, fetch(new_date)?)));
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
+    }
+    let (old, new): (Vec<LessonInfo>, Vec<LessonInfo>) = lessons?;
+    Ok(diff_lessons(&old, &new, teacher_match))
+}
 
-    log::info!("Initialization succeeded!");
+fn export_reference(args: &ExportReferenceArgs) -> Result<()> {
+    let week = args.week_of.week(Weekday::Mon);
+    let (monday, sunday): (NaiveDate, NaiveDate) = (week.first_day(), week.last_day());
+    let (untis_client, timetable) = args.login.login()?;
+    let days = untis_client.fetch_entries(monday, sunday, timetable);
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
+    }
+    let mut lessons: Vec<LessonInfo> = Vec::new();
+    for day in &days? {
+        lessons.extend(extract_all_lessons(day)?);
+    }
 
-    let mut sequential_errors = 0;
+    fs::write(&args.output, serde_json::to_string_pretty(&lessons)?)
+        .with_context(|| format!("Could not write {}", args.output.display()))?;
+    log::info!(
+        "Saved {} lessons of the week {monday} to {sunday} to {}.",
+        lessons.len(),
+        args.output.display()
+    );
+    Ok(())
+}
 
-    while sequential_errors < 5 {
-        if let Err(e) = app.iteration() {
-            let e = format!("{e:?}");
-            app.discord_client.send_error(&e);
-            sequential_errors += 1;
-        } else {
-            sequential_errors = 0;
+fn serve_ics(args: &ServeIcsArgs) -> Result<()> {
+    let refresh = Duration::from_secs(args.refresh_mins * 60);
+    loop {
+        // Sessions expire between refreshes, so log in every time.
+        match export_ics(args) {
+            Ok(count) => log::info!("Exported {count} lessons to {}.", args.output.display()),
+            Err(e) => log::error!("Could not export ICS file: {e:?}"),
         }
+        sleep(refresh);
     }
-
-    app.discord_client
-        .send_error("App failed too many times in a row; shutting down.");
-    bail!("App failed {sequential_errors} times in a row");
 }
 
-fn get_relevant_date(now: DateTime<Tz>) -> NaiveDate {
-    let mut date: NaiveDate = now.date_naive();
+/// Fetch the upcoming lessons and atomically replace the ICS file, returning the lesson count.
+fn export_ics(args: &ServeIcsArgs) -> Result<usize> {
+    let (untis_client, timetable) = args.login.login()?;
+    let timezone: Option<Tz> = args.timezone.or_else(|| {
+        untis_client
+            .fetch_school_timezone()
+            .inspect_err(|e| {
+                log::warn!("Could not detect school timezone; writing local times: {e:?}")
+            })
+            .ok()
+    });
+    let start: NaiveDate = Utc::now()
+        .with_timezone(&timezone.unwrap_or(Tz::UTC))
+        .date_naive();
+    let end: NaiveDate = start
+        .checked_add_days(Days::new(args.days - 1))
+        .context("Export range is out of range")?;
+    let entries = untis_client.fetch_entries_chunked(start, end, timetable);
+    if let Err(e) = untis_client.logout() {
+        log::warn!("Could not log out of Untis: {e:?}");
+    }
+    if entries.days.is_empty() && !entries.failed.is_empty() {
+        bail!("Could not fetch any entries; keeping the previous file");
+    }
+    if !entries.failed.is_empty() {
+        let failed: Vec<String> = entries
+            .failed
+            .iter()
+            .map(|(start, end)| format!("{start} to {end}"))
+            .collect();
+        log::warn!("Exporting without the days from {}.", failed.join(", "));
+    }
+    let lessons: Vec<LessonInfo> = entries
+        .days
+        .iter()
+        .flat_map(extract_all_lessons_lenient)
+        .collect();
+
+    // Calendar clients must never see a half-written file.
+    json_file::write_atomically(&args.output, lessons_to_ics(&lessons, timezone))?;
+    Ok(lessons.len())
+}
 
-    if now.hour() >= 18 {
-        // After 18:00, show changes for tomorrow instead of today.
-        date = date.checked_add_days(Days::new(1)).unwrap();
+fn run(args: RunArgs) -> Result<()> {
+    if args.dump_config {
+        return dump_config(args);
+    }
+    let forum: Option<Forum> = args.forum.then(|| {
+        args.forum_tag
+            .iter()
+            .fold(Forum::new(), |forum, (kind, id)| forum.tag(kind, *id))
+    });
+    let mut tls = TlsConfig::new();
+    if let Some(path) = &args.ca_cert {
+        tls = tls.root_cert_file(path)?;
+    }
+    if args.danger_accept_invalid_certs {
+        tls = tls.danger_accept_invalid_certs();
     }
+    if args.stdout {
+        log::info!("Printing changes to stdout instead of sending them to Discord.");
+    }
+    let resources_cache: PathBuf = args
+        .resources_cache
+        .clone()
+        .map_or_else(|| paths::default_cache_file("resources.json"), Ok)?;
+    let range: WatchRange = match (args.watch_start, args.watch_end, args.watch_days_ahead) {
+        (Some(start), Some(end), _) => WatchRange::fixed(start, end)?,
+        (_, _, Some(days)) => WatchRange::DaysAhead(days),
+        _ => WatchRange::Relevant,
+    };
+    let resource_type = args.resource_type;
+    let default_title = match resource_type {
+        ResourceType::Teacher | ResourceType::Room => Template::teacher_title,
+        _ => Template::default_title,
+    };
+    let recent: Option<RecentNotifications> = match args.status_addr {
+        Some(addr) => {
+            let recent = RecentNotifications::new(args.recent_size);
+            status_server::spawn(addr, recent.clone())?;
+            Some(recent)
+        }
+        None => None,
+    };
+    let state_store: Option<Rc<StateStore>> = match args.on_startup {
+        StartupMode::Diff => {
+            let path: PathBuf = args
+                .state_file
+                .clone()
+                .map_or_else(|| paths::default_state_file("state.json"), Ok)?;
+            Some(Rc::new(StateStore::new(path)))
+        }
+        StartupMode::Silent | StartupMode::Announce => None,
+    };
+    let live_message_file: Option<PathBuf> = if args.live_message {
+        let path: PathBuf = args
+            .live_message_file
+            .clone()
+            .map_or_else(|| paths::default_state_file("live_messages.json"), Ok)?;
+        Some(path)
+    } else {
+        None
+    };
+    let options = WatchOptions {
+        forum,
+        lesson_fields: args.embed_fields.clone(),
+        notify_style: args.notify_style,
+        label: args.label.clone(),
+        identities: args
+            .identity
+            .iter()
+            .fold(Identities::new(), |identities, (kind, identity)| {
+                identities.with(kind, identity.clone())
+            }),
+        coalesce_window: Duration::from_secs(args.coalesce_secs),
+        webhook_timeout: Duration::from_secs(args.webhook_timeout_secs),
+        relogin_interval: Duration::from_mins(args.relogin_mins),
+        error_repeat_window: Duration::from_secs(args.error_repeat_mins * 60),
+        iteration_timeout: Duration::from_secs(args.iteration_timeout_secs),
+        fallback_webhook_url: args.fallback_webhook_url.clone(),
+        stdout: args.stdout,
+        baseline: args.baseline,
+        tls,
+        allowed_hosts: HostAllowlist::new(args.allowed_host_suffix.clone()),
+        resource_cache: Rc::new(ResourceCache::new(resources_cache, args.refresh_resources)),
+        deep_link: args.deep_link,
+        title_template: args.title_template.clone().unwrap_or_else(default_title),
+        body_template: args
+            .body_template
+            .clone()
+            .unwrap_or_else(Template::default_body),
+        date_format: args.date_format.clone(),
+        range,
+        resource_type,
+        lenient_extract: args.lenient_extract,
+        skip_past_lessons: args.skip_past_lessons,
+        teacher_match: args.teacher_match_by,
+        assignment_keywords: args
+            .assignment_keyword
+            .as_ref()
+            .map_or_else(AssignmentKeywords::default, AssignmentKeywords::new),
+        no_school_statuses: args.no_school_status.iter().copied().collect(),
+        digest_at: args.digest_at,
+        pause_file: args.pause_file.clone(),
+        notify_weekdays: args.notify_weekdays.iter().copied().collect(),
+        merge_double_periods: args.merge_double_periods,
+        notify_exams: args.notify_exams,
+        notify_messages: args.notify_messages,
+        publish_lookahead_days: args.publish_lookahead_days,
+        recent,
+        on_startup: args.on_startup,
+        #[cfg(feature = "image")]
+        timetable_image: args
+            .timetable_image_font
+            .as_deref()
+            .map(TimetableRenderer::from_font_file)
+            .transpose()?,
+        state_store,
+        live_message_file,
+    };
+
+    let off_hours_sleep = Duration::from_secs(args.max_lookahead_sleep);
 
-    date
+    let configs: Vec<WatchConfig> = match &args.config {
+        Some(path) => config::load_watches(path)?,
+        None => vec![args.into_watch_config()?],
+    };
+    let watches = configs
+        .into_iter()
+        .map(|config| Watch::new(config, &options))
+        .collect::<Result<Vec<_>>>()?;
+
+    log::info!(
+        "Initialization succeeded; watching {} timetable(s).",
+        watches.len()
+    );
+    App::new(watches, off_hours_sleep).run()
 }
 
-fn get_sleep_time(now: DateTime<Utc>) -> Duration {
-    let time: NaiveTime = now.time();
-    let secs = match time.hour() {
-        7..8 => 4,
-        6..11 => 20,
-        11..16 => 40,
-        _ => 200,
+/// Print the watches and settings `run` would use, after applying defaults.
+fn dump_config(args: RunArgs) -> Result<()> {
+    let settings = serde_json::to_value(&args)?;
+    let configs: Vec<WatchConfig> = match &args.config {
+        Some(path) => config::load_watches(path)?,
+        None => vec![args.into_watch_config()?],
     };
-    Duration::from_secs(secs)
+    let dump = serde_json::json!({
+        "watches": configs,
+        "settings": settings,
+    });
+    println!("{}", serde_json::to_string_pretty(&dump)?);
+    Ok(())
 }
 
-// TODO: refresh token after x minutes
+impl RunArgs {
+    /// Build the single watch described by the command line arguments.
+    fn into_watch_config(self) -> Result<WatchConfig> {
+        let discord_webhook_url = match (self.discord_webhook_url, &self.discord_webhook_url_file) {
+            (Some(url), _) => Some(url),
+            (None, Some(path)) => Some(read_webhook_url_file(path)?),
+            (None, None) => None,
+        };
+        // clap ensures these are present unless a config file is given
+        Ok(WatchConfig {
+            school: self.school.context("Missing --school")?,
+            username: self.username.context("Missing --username")?,
+            password: self.password.context("Missing --password")?,
+            server: self.server,
+            timetable_id: self.timetable_id,
+            class: self.class,
+            discord_webhook_url,
+            discord_bot_token: self.discord_bot_token,
+            discord_channel_id: self.discord_channel_id,
+            webhook_url: self.webhook_url,
+            webhook_headers: self.webhook_header.into_iter().collect(),
+            timezone: self.timezone,
+            reference_file: self.reference_file,
+        })
+    }
+}
+
+/// Read a Discord WebHook URL from a file, ignoring surrounding whitespace.
+fn read_webhook_url_file(path: &Path) -> Result<Url> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Could not read WebHook URL file {}", path.display()))?;
+    let url = Url::parse(text.trim())
+        .with_context(|| format!("Invalid WebHook URL in {}", path.display()))?;
+    WebhookCredentials::from_url(&url)
+        .with_context(|| format!("Invalid WebHook URL in {}", path.display()))?;
+    Ok(url)
+}
+
+fn parse_header(string: &str) -> Result<(String, String)> {
+    let (name, value) = string
+        .split_once(':')
+        .context("Expected format <name>: <value>")?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+fn parse_forum_tag(string: &str) -> Result<(String, u64)> {
+    let (kind, id) = string
+        .split_once('=')
+        .context("Expected format <kind>=<tag id>")?;
+    if !LessonChange::KINDS.contains(&kind) {
+        bail!(
+            "Unknown change kind {kind:?}; expected one of {:?}",
+            LessonChange::KINDS
+        );
+    }
+    let id: u64 = id.parse().context("Invalid forum tag ID")?;
+    Ok((kind.to_string(), id))
+}
+
+fn parse_identity(string: &str) -> Result<(String, Identity)> {
+    let (kind, identity) = string
+        .split_once('=')
+        .context("Expected format <kind>=<username>[|<avatar URL>]")?;
+    if !LessonChange::KINDS.contains(&kind) {
+        bail!(
+            "Unknown change kind {kind:?}; expected one of {:?}",
+            LessonChange::KINDS
+        );
+    }
+    Ok((kind.to_string(), identity.parse()?))
+}
@@ -0,0 +1,87 @@
+use crate::LessonChange;
+
+/// Merges the changes to double periods (back-to-back lessons that are identical apart from
+/// their times) into a single change spanning both, so they are only reported once.
+///
+/// Changes are only merged if they are the same apart from the lesson times, and the second
+/// lesson starts exactly when the first ends. Diff the unmerged lessons, so a change to only
+/// one half of a double period is still reported as such. The merged change keeps the period
+/// number of the first lesson.
+#[must_use]
+pub fn merge_double_periods(changes: Vec<LessonChange>) -> Vec<LessonChange> {
+    let mut merged: Vec<LessonChange> = Vec::with_capacity(changes.len());
+    for change in changes {
+        if let Some(first) = merged
+            .iter_mut()
+            .rev()
+            .find(|first| continues(first, &change))
+        {
+            first.lesson_mut().end = change.lesson().end;
+        } else {
+            merged.push(change);
+        }
+    }
+    merged
+}
+
+/// Whether `next` is the same change as `first`, to the lesson directly following it.
+fn continues(first: &LessonChange, next: &LessonChange) -> bool {
+    let first_lesson = first.lesson();
+    if first_lesson.end != next.lesson().datetime {
+        return false;
+    }
+    let mut next_as_first = next.clone();
+    let lesson = next_as_first.lesson_mut();
+    lesson.datetime = first_lesson.datetime;
+    lesson.end = first_lesson.end;
+    lesson.period = first_lesson.period;
+    next_as_first == *first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        LessonInfo, TeacherMatch, diff_lessons, test_util::lesson, untis::entries::Status,
+    };
+
+    fn cancelled(lesson: &LessonInfo) -> LessonInfo {
+        LessonInfo {
+            status: Status::Cancelled,
+            ..lesson.clone()
+        }
+    }
+
+    #[test]
+    fn cancelled_double_periods_are_reported_once() {
+        let old = [lesson((8, 0), "Maths"), lesson((8, 45), "Maths")];
+        let new = [cancelled(&old[0]), cancelled(&old[1])];
+
+        let changes = merge_double_periods(diff_lessons(&old, &new, TeacherMatch::LongName));
+        assert_eq!(
+            changes,
+            [LessonChange::Cancelled {
+                lesson: LessonInfo {
+                    end: new[1].end,
+                    ..new[0].clone()
+                },
+                old_status: Status::Regular,
+            }]
+        );
+    }
+
+    #[test]
+    fn half_cancelled_double_periods_report_the_cancelled_half() {
+        let old = [lesson((8, 0), "Maths"), lesson((8, 45), "Maths")];
+        let new = [old[0].clone(), cancelled(&old[1])];
+
+        let changes = merge_double_periods(diff_lessons(&old, &new, TeacherMatch::LongName));
+        assert_eq!(
+            changes,
+            [LessonChange::Cancelled {
+                lesson: new[1].clone(),
+                old_status: Status::Regular,
+            }]
+        );
+    }
+}
@@ -0,0 +1,618 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use colored::{Color, Colorize as _};
+use serde::Serialize;
+
+use crate::{
+    DateFormat, LessonChange, LessonInfo,
+    discord::DiscordClient,
+    or_absent,
+    untis::{exams::ExamChange, messages::DayMessage},
+};
+
+/// A notification that could not be delivered, together with the backend that failed.
+#[derive(Debug)]
+pub struct NotifyError {
+    /// The name of the backend, e.g. `Discord`.
+    pub backend: &'static str,
+    pub cause: anyhow::Error,
+}
+
+impl NotifyError {
+    #[must_use]
+    pub fn new(backend: &'static str, cause: impl Into<anyhow::Error>) -> Self {
+        Self {
+            backend,
+            cause: cause.into(),
+        }
+    }
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.backend)
+    }
+}
+
+impl Error for NotifyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.cause.as_ref())
+    }
+}
+
+/// Somewhere lesson changes and errors can be reported to.
+pub trait Notifier {
+    /// Report a single lesson change.
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError>;
+
+    /// Report an error. Failing to do so is only logged.
+    fn send_error(&self, err_message: &str);
+
+    /// Report a likely misconfiguration that does not stop the bot. Failing to do so is only
+    /// logged.
+    fn send_warning(&self, message: &str);
+
+    /// Report all changes of a day at once, compared to the originally published timetable.
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError>;
+
+    /// Report a day's whole timetable as it currently is.
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError>;
+
+    /// Report an exam that was added, changed or removed.
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError>;
+
+    /// Report that the timetable of these (previously empty) days was published.
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError>;
+
+    /// Report a new school-wide message of the day.
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError>;
+}
+
+/// Describe newly published days, e.g. `The timetable for 2026-10-19 to 2026-10-23 is now
+/// available.`
+#[must_use]
+pub fn published_description(dates: &[NaiveDate]) -> String {
+    let contiguous: bool = dates.windows(2).all(|x| x[0].succ_opt() == Some(x[1]));
+    let days = match dates {
+        [] => return "No new timetable was published.".to_string(),
+        [date] => date.to_string(),
+        [first, .., last] if contiguous => format!("{first} to {last}"),
+        _ => dates
+            .iter()
+            .map(NaiveDate::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    format!("The timetable for {days} is now available.")
+}
+
+impl Notifier for DiscordClient {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        Self::send_change(self, change).map_err(|e| NotifyError::new("Discord", e))
+    }
+
+    fn send_error(&self, err_message: &str) {
+        Self::send_error(self, err_message);
+    }
+
+    fn send_warning(&self, message: &str) {
+        Self::send_warning(self, message);
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        Self::send_digest(self, date, changes).map_err(|e| NotifyError::new("Discord", e))
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        Self::send_timetable(self, date, lessons).map_err(|e| NotifyError::new("Discord", e))
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        Self::send_exam_change(self, change).map_err(|e| NotifyError::new("Discord", e))
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        Self::send_published(self, dates).map_err(|e| NotifyError::new("Discord", e))
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        Self::send_day_message(self, message).map_err(|e| NotifyError::new("Discord", e))
+    }
+}
+
+/// Prints timestamped, colored change lines to stdout, for use as a terminal monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdoutNotifier {
+    name: String,
+    date_format: DateFormat,
+}
+
+impl StdoutNotifier {
+    /// The name is printed in front of every line to tell watches apart.
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            date_format: DateFormat::default(),
+        }
+    }
+
+    /// Show lesson dates in this format.
+    #[must_use]
+    pub fn with_date_format(mut self, date_format: DateFormat) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    fn print(&self, color: Color, title: &str, message: &str) {
+        let time = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string().dimmed();
+        let name = self.name.dimmed();
+        let title = title.color(color).bold();
+        println!("{time} [{name}] {title} {message}");
+    }
+}
+
+impl Notifier for StdoutNotifier {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        let lesson = change.lesson();
+        let color = match change {
+            LessonChange::Cancelled { .. } => Color::Red,
+            LessonChange::Reinstated { .. } | LessonChange::Reverted { .. } => Color::Green,
+            LessonChange::Substitution { .. }
+            | LessonChange::MeetingLink { .. }
+            | LessonChange::Assignment { .. } => Color::Cyan,
+            _ => Color::Yellow,
+        };
+        let period = lesson
+            .period
+            .map_or_else(String::new, |x| format!(" (period {x})"));
+        let class = lesson
+            .class
+            .as_ref()
+            .map_or_else(String::new, |x| format!(" ({x})"));
+        let group = lesson
+            .group
+            .as_ref()
+            .map_or_else(String::new, |x| format!(" — {x}"));
+        let message = format!(
+            "{}{group}{class} at {}{period}: {}",
+            or_absent(lesson.subject.as_deref()),
+            self.date_format.format(lesson.datetime),
+            change.description().replace('\n', "; "),
+        );
+        self.print(color, change.title(), &message);
+        Ok(())
+    }
+
+    fn send_error(&self, err_message: &str) {
+        self.print(Color::Red, "Error", err_message);
+    }
+
+    fn send_warning(&self, message: &str) {
+        self.print(Color::Yellow, "Warning", message);
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        let title = format!("Digest for {date}");
+        if changes.is_empty() {
+            self.print(Color::Green, &title, "No changes.");
+        }
+        for change in changes {
+            self.print(Color::Blue, &title, &change.summary_line());
+        }
+        Ok(())
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        let title = format!("Timetable for {date}");
+        if lessons.is_empty() {
+            self.print(Color::Blue, &title, "No lessons.");
+        }
+        for lesson in lessons {
+            self.print(Color::Blue, &title, &lesson.summary_line());
+        }
+        Ok(())
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        let color = match change {
+            ExamChange::Removed { .. } => Color::Green,
+            _ => Color::Magenta,
+        };
+        self.print(
+            color,
+            change.title(),
+            &change.description().replace('\n', "; "),
+        );
+        Ok(())
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        self.print(
+            Color::Blue,
+            "Timetable Published",
+            &published_description(dates),
+        );
+        Ok(())
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        let body = format!("{}: {}", message.subject, message.plain_text());
+        self.print(
+            Color::Magenta,
+            "Message of the Day",
+            &body.replace('\n', "; "),
+        );
+        Ok(())
+    }
+}
+
+/// Wraps another notifier, holding back errors identical to the last one sent within a time
+/// window, so a lasting outage doesn't flood the channel.
+///
+/// The next error let through mentions how many identical ones were held back.
+pub struct ThrottledNotifier {
+    inner: Box<dyn Notifier>,
+    window: Duration,
+    last_error: RefCell<Option<LastError>>,
+}
+
+struct LastError {
+    message: String,
+    sent_at: Instant,
+    suppressed: u32,
+}
+
+impl ThrottledNotifier {
+    #[must_use]
+    pub fn new(inner: Box<dyn Notifier>, window: Duration) -> Self {
+        Self {
+            inner,
+            window,
+            last_error: RefCell::new(None),
+        }
+    }
+}
+
+impl Notifier for ThrottledNotifier {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        self.inner.send_change(change)
+    }
+
+    fn send_error(&self, err_message: &str) {
+        let mut last_error = self.last_error.borrow_mut();
+        let suppressed = match last_error.as_mut() {
+            Some(last) if last.message == err_message && last.sent_at.elapsed() < self.window => {
+                last.suppressed += 1;
+                log::error!("{err_message} (not sent again, identical to the last error)");
+                return;
+            }
+            Some(last) if last.message == err_message => last.suppressed,
+            _ => 0,
+        };
+        if suppressed == 0 {
+            self.inner.send_error(err_message);
+        } else {
+            self.inner.send_error(&format!(
+                "{err_message}\n(occurred {suppressed} more times since the last notification)"
+            ));
+        }
+        *last_error = Some(LastError {
+            message: err_message.to_string(),
+            sent_at: Instant::now(),
+            suppressed: 0,
+        });
+    }
+
+    fn send_warning(&self, message: &str) {
+        self.inner.send_warning(message);
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        self.inner.send_digest(date, changes)
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        self.inner.send_timetable(date, lessons)
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        self.inner.send_exam_change(change)
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        self.inner.send_published(dates)
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        self.inner.send_day_message(message)
+    }
+}
+
+/// Sends to a secondary notifier whenever the primary one failed (after its own retries), so
+/// alerts still get through while one channel is down.
+///
+/// Errors and warnings can't tell whether they were delivered; they go to the fallback too
+/// for as long as the primary's last send failed.
+pub struct FallbackNotifier {
+    primary: Box<dyn Notifier>,
+    fallback: Box<dyn Notifier>,
+    primary_down: Cell<bool>,
+}
+
+impl FallbackNotifier {
+    #[must_use]
+    pub fn new(primary: Box<dyn Notifier>, fallback: Box<dyn Notifier>) -> Self {
+        Self {
+            primary,
+            fallback,
+            primary_down: Cell::new(false),
+        }
+    }
+
+    /// Try the primary, and if it fails the fallback, returning the primary's error if both do.
+    fn send(
+        &self,
+        send: impl Fn(&dyn Notifier) -> Result<(), NotifyError>,
+    ) -> Result<(), NotifyError> {
+        let Err(error) = send(&*self.primary) else {
+            self.primary_down.set(false);
+            return Ok(());
+        };
+        self.primary_down.set(true);
+        log::warn!("{error}: {:#}; escalating to the fallback", error.cause);
+        match send(&*self.fallback) {
+            Ok(()) => Ok(()),
+            Err(fallback_error) => {
+                log::error!("Fallback {fallback_error}: {:#}", fallback_error.cause);
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Notifier for FallbackNotifier {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_change(change))
+    }
+
+    fn send_error(&self, err_message: &str) {
+        self.primary.send_error(err_message);
+        if self.primary_down.get() {
+            self.fallback.send_error(err_message);
+        }
+    }
+
+    fn send_warning(&self, message: &str) {
+        self.primary.send_warning(message);
+        if self.primary_down.get() {
+            self.fallback.send_warning(message);
+        }
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_digest(date, changes))
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_timetable(date, lessons))
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_exam_change(change))
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_published(dates))
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        self.send(|notifier| notifier.send_day_message(message))
+    }
+}
+
+/// A notification as kept in [`RecentNotifications`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecentNotification {
+    /// The name of the watch that sent it.
+    pub watch: String,
+    pub title: String,
+    pub time: DateTime<Utc>,
+    pub body: String,
+}
+
+/// The last few change notifications (of all watches), oldest first, shareable across threads.
+#[derive(Debug, Clone)]
+pub struct RecentNotifications {
+    buffer: Arc<Mutex<VecDeque<RecentNotification>>>,
+    capacity: usize,
+}
+
+impl RecentNotifications {
+    /// Keep at most `capacity` notifications, dropping the oldest ones.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn push(&self, notification: RecentNotification) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        if buffer.len() >= self.capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(notification);
+    }
+
+    /// A copy of the kept notifications, oldest first.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<RecentNotification> {
+        let buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        buffer.iter().cloned().collect()
+    }
+}
+
+/// Wraps another notifier, remembering every change it sends in [`RecentNotifications`].
+pub struct RecordingNotifier {
+    inner: Box<dyn Notifier>,
+    name: String,
+    recent: RecentNotifications,
+}
+
+impl RecordingNotifier {
+    /// The name tells the watches apart in the recorded notifications.
+    #[must_use]
+    pub fn new(
+        inner: Box<dyn Notifier>,
+        name: impl Into<String>,
+        recent: RecentNotifications,
+    ) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            recent,
+        }
+    }
+
+    /// Only called once the inner notifier succeeded, so failed sends aren't listed.
+    fn record(&self, title: String, body: String) {
+        self.recent.push(RecentNotification {
+            watch: self.name.clone(),
+            title,
+            time: Utc::now(),
+            body,
+        });
+    }
+}
+
+impl Notifier for RecordingNotifier {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        self.inner.send_change(change)?;
+        self.record(change.title().to_string(), change.description());
+        Ok(())
+    }
+
+    fn send_error(&self, err_message: &str) {
+        self.inner.send_error(err_message);
+    }
+
+    fn send_warning(&self, message: &str) {
+        self.inner.send_warning(message);
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        self.inner.send_digest(date, changes)?;
+        let body = changes
+            .iter()
+            .map(LessonChange::summary_line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.record(format!("Digest for {date}"), body);
+        Ok(())
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        self.inner.send_timetable(date, lessons)
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        self.inner.send_exam_change(change)?;
+        self.record(change.title().to_string(), change.description());
+        Ok(())
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        self.inner.send_published(dates)?;
+        self.record(
+            "Timetable Published".to_string(),
+            published_description(dates),
+        );
+        Ok(())
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        self.inner.send_day_message(message)?;
+        self.record(message.subject.clone(), message.plain_text());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    /// Fails to send anything, like a webhook that is down.
+    struct FailingNotifier;
+
+    impl FailingNotifier {
+        fn fail() -> Result<(), NotifyError> {
+            Err(NotifyError::new("Test", anyhow!("down")))
+        }
+    }
+
+    impl Notifier for FailingNotifier {
+        fn send_change(&self, _: &LessonChange) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+
+        fn send_error(&self, _: &str) {}
+
+        fn send_warning(&self, _: &str) {}
+
+        fn send_digest(&self, _: NaiveDate, _: &[LessonChange]) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+
+        fn send_timetable(&self, _: NaiveDate, _: &[LessonInfo]) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+
+        fn send_exam_change(&self, _: &ExamChange) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+
+        fn send_published(&self, _: &[NaiveDate]) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+
+        fn send_day_message(&self, _: &DayMessage) -> Result<(), NotifyError> {
+            Self::fail()
+        }
+    }
+
+    #[test]
+    fn records_only_delivered_notifications() {
+        let dates = [NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()];
+        let recent = RecentNotifications::new(10);
+
+        let failing = RecordingNotifier::new(Box::new(FailingNotifier), "failing", recent.clone());
+        assert!(failing.send_published(&dates).is_err());
+        assert!(recent.snapshot().is_empty());
+
+        let stdout = RecordingNotifier::new(
+            Box::new(StdoutNotifier::new("stdout")),
+            "stdout",
+            recent.clone(),
+        );
+        stdout.send_published(&dates).unwrap();
+        let recorded = recent.snapshot();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].watch, "stdout");
+    }
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+
+/// Where a cache file goes by default (`$XDG_CACHE_HOME/webuntis` on Linux).
+pub fn default_cache_file(file_name: &str) -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "webuntis")
+        .context("Could not determine home directory for the default cache location")?;
+    Ok(dirs.cache_dir().join(file_name))
+}
+
+/// Where a state file goes by default (`$XDG_STATE_HOME/webuntis` on Linux, the local data
+/// directory elsewhere), for data that should survive clearing the cache.
+pub fn default_state_file(file_name: &str) -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "webuntis")
+        .context("Could not determine home directory for the default state location")?;
+    let dir = dirs.state_dir().unwrap_or_else(|| dirs.data_local_dir());
+    Ok(dir.join(file_name))
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use webuntis::untis::{UntisClient, resources::Resource};
+
+use crate::json_file::{load_json_or_default, save_json_atomically};
+
+/// Cached resource lists are refetched after this long.
+const CACHE_TTL: TimeDelta = TimeDelta::days(7);
+
+/// A file caching every account's list of timetables between runs.
+///
+/// The file holds one entry per account (keyed by `username@school`), so watches of
+/// multiple accounts can share it.
+pub struct ResourceCache {
+    path: PathBuf,
+    /// Ignore cached entries and always refetch the lists.
+    refresh: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    accounts: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    fetched_at: DateTime<Utc>,
+    resources: Vec<Resource>,
+}
+
+impl ResourceCache {
+    pub const fn new(path: PathBuf, refresh: bool) -> Self {
+        Self { path, refresh }
+    }
+
+    /// The account's list of timetables, from the cache if it is fresh enough.
+    pub fn timetables(&self, account: &str, untis_client: &UntisClient) -> Result<Vec<Resource>> {
+        let mut file: CacheFile = self.load().unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable resources cache: {e:?}");
+            CacheFile::default()
+        });
+
+        if !self.refresh
+            && let Some(entry) = file.accounts.get(account)
+            && Utc::now() - entry.fetched_at < CACHE_TTL
+        {
+            log::debug!("Using cached resources for {account}.");
+            return Ok(entry.resources.clone());
+        }
+
+        let resources: Vec<Resource> = untis_client.list_timetables()?;
+        let entry = CacheEntry {
+            fetched_at: Utc::now(),
+            resources: resources.clone(),
+        };
+        file.accounts.insert(account.to_string(), entry);
+        if let Err(e) = self.save(&file) {
+            log::warn!("Could not update resources cache: {e:?}");
+        }
+        Ok(resources)
+    }
+
+    fn load(&self) -> Result<CacheFile> {
+        load_json_or_default(&self.path, "cache file")
+    }
+
+    fn save(&self, file: &CacheFile) -> Result<()> {
+        save_json_atomically(&self.path, file, "cache file")
+    }
+}
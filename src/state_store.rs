@@ -0,0 +1,47 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use webuntis::LessonInfo;
+
+use crate::json_file::{load_json_or_default, save_json_atomically};
+
+/// The lessons of every watched day, as last seen.
+pub type SavedDays = BTreeMap<NaiveDate, Vec<LessonInfo>>;
+
+/// A file keeping the last seen lessons of every watch between runs, so changes made while the
+/// bot was not running can be reported on startup.
+///
+/// The file holds one entry per watch (keyed by its name), so multiple watches can share it.
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    pub const fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The days last saved for the watch; none if there are none.
+    pub fn load(&self, watch: &str) -> Result<SavedDays> {
+        let mut file = self.load_file()?;
+        Ok(file.remove(watch).unwrap_or_default())
+    }
+
+    /// Replace the days saved for the watch.
+    pub fn save(&self, watch: &str, days: SavedDays) -> Result<()> {
+        let mut file = self.load_file().unwrap_or_else(|e| {
+            log::warn!("Overwriting unreadable state file: {e:?}");
+            HashMap::new()
+        });
+        file.insert(watch.to_string(), days);
+        save_json_atomically(&self.path, &file, "state file")
+    }
+
+    fn load_file(&self) -> Result<HashMap<String, SavedDays>> {
+        load_json_or_default(&self.path, "state file")
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use webuntis::notify::RecentNotifications;
+
+/// How long to wait for a client to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve the recent notifications as JSON at `/recent`, in a background thread.
+///
+/// The server is read-only: anything but a GET request is rejected.
+pub fn spawn(addr: SocketAddr, recent: RecentNotifications) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Could not listen on {addr}"))?;
+    log::info!("Serving recent notifications at http://{addr}/recent");
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream
+                .map_err(anyhow::Error::from)
+                .and_then(|stream| handle(stream, &recent));
+            if let Err(e) = result {
+                log::warn!("Could not answer status request: {e:#}");
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, recent: &RecentNotifications) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/recent")) => ("200 OK", serde_json::to_string(&recent.snapshot())?),
+        (Some("GET"), _) => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            r#"{"error":"method not allowed"}"#.to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
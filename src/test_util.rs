@@ -0,0 +1,40 @@
+//! Helpers shared by the unit tests.
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{
+    LessonInfo,
+    untis::entries::{EntryType, Status},
+};
+
+/// A regular 45 minute lesson on Monday, 2026-10-19, in room 12 with Mr Smith.
+pub fn lesson(start: (u32, u32), subject: &str) -> LessonInfo {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+    let datetime = date.and_hms_opt(start.0, start.1, 0).unwrap();
+    LessonInfo {
+        status: Status::Regular,
+        entry_type: EntryType::NormalTeachingPeriod,
+        datetime,
+        end: datetime + TimeDelta::minutes(45),
+        period: None,
+        web_url: None,
+        subject: Some(subject.to_string()),
+        subject_status: Status::Regular,
+        subject_color: None,
+        class: None,
+        group: None,
+        teacher: Some("Mr Smith".to_string()),
+        teacher_short_name: Some("SMI".to_string()),
+        teacher_status: Status::Regular,
+        room: Some("12".to_string()),
+        room_status: Status::Regular,
+        room_location: None,
+        info: None,
+        lesson_info: None,
+        lesson_text: None,
+        substitution_text: None,
+        notes: None,
+        texts: Vec::new(),
+        meeting_url: None,
+    }
+}
@@ -0,0 +1,228 @@
+use std::{fmt, fs, path::Path, sync::Arc};
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use anyhow::{Context, Result};
+use tiny_skia::{Color, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke, Transform};
+
+use crate::{ABSENT, LessonInfo, or_absent, untis::entries::Status};
+
+const FONT_SIZE: f32 = 18.0;
+const ROW_HEIGHT: u32 = 32;
+const PADDING: u32 = 12;
+
+/// The table's columns with their widths in pixels.
+const COLUMNS: [(&str, u32); 5] = [
+    ("Period", 70),
+    ("Time", 130),
+    ("Subject", 200),
+    ("Teacher", 200),
+    ("Room", 160),
+];
+
+const TEXT_COLOR: [u8; 3] = [32, 34, 37];
+const HEADER_COLOR: [u8; 3] = [146, 23, 237];
+const STRIPE_COLOR: [u8; 3] = [242, 243, 245];
+const CHANGED_COLOR: [u8; 3] = [255, 240, 200];
+const CANCELLED_COLOR: [u8; 3] = [250, 215, 215];
+
+/// Renders a day's lessons into a PNG table, one row per lesson.
+///
+/// Cancelled lessons are struck through, other irregular ones are highlighted.
+#[derive(Clone)]
+pub struct TimetableRenderer {
+    font: Arc<FontVec>,
+}
+
+impl fmt::Debug for TimetableRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimetableRenderer").finish_non_exhaustive()
+    }
+}
+
+impl TimetableRenderer {
+    /// Use the TrueType/OpenType font at this path for all text.
+    pub fn from_font_file(path: &Path) -> Result<Self> {
+        let data = fs::read(path)
+            .with_context(|| format!("Could not read font file {}", path.display()))?;
+        let font = FontVec::try_from_vec(data)
+            .with_context(|| format!("Invalid font file {}", path.display()))?;
+        Ok(Self {
+            font: Arc::new(font),
+        })
+    }
+
+    /// Render the lessons below the title, returning the PNG-encoded image.
+    pub fn render_png(&self, title: &str, lessons: &[LessonInfo]) -> Result<Vec<u8>> {
+        let width: u32 = COLUMNS.iter().map(|(_, w)| w).sum::<u32>() + 2 * PADDING;
+        // Title, header and at least one row (for "No lessons").
+        let rows = u32::try_from(lessons.len().max(1)).context("Too many lessons")?;
+        let height: u32 = (rows + 2) * ROW_HEIGHT + 2 * PADDING;
+        let mut pixmap = Pixmap::new(width, height).context("Invalid image size")?;
+        pixmap.fill(Color::WHITE);
+
+        let mut y: u32 = PADDING;
+        self.draw_text(
+            &mut pixmap,
+            title,
+            PADDING,
+            y,
+            width - 2 * PADDING,
+            TEXT_COLOR,
+        );
+        y += ROW_HEIGHT;
+
+        fill_rect(
+            &mut pixmap,
+            PADDING,
+            y,
+            width - 2 * PADDING,
+            ROW_HEIGHT,
+            HEADER_COLOR,
+        );
+        let mut x: u32 = PADDING;
+        for (name, column_width) in COLUMNS {
+            self.draw_text(&mut pixmap, name, x, y, column_width, [255, 255, 255]);
+            x += column_width;
+        }
+        y += ROW_HEIGHT;
+
+        if lessons.is_empty() {
+            self.draw_text(
+                &mut pixmap,
+                "No lessons.",
+                PADDING,
+                y,
+                width - 2 * PADDING,
+                TEXT_COLOR,
+            );
+        }
+        for (i, lesson) in lessons.iter().enumerate() {
+            let cancelled: bool = lesson.status == Status::Cancelled;
+            let background = if cancelled {
+                Some(CANCELLED_COLOR)
+            } else if !lesson.status.is_normal() {
+                Some(CHANGED_COLOR)
+            } else if i % 2 == 1 {
+                Some(STRIPE_COLOR)
+            } else {
+                None
+            };
+            if let Some(color) = background {
+                fill_rect(
+                    &mut pixmap,
+                    PADDING,
+                    y,
+                    width - 2 * PADDING,
+                    ROW_HEIGHT,
+                    color,
+                );
+            }
+
+            let cells: [String; 5] = [
+                lesson.period.map_or_else(String::new, |p| p.to_string()),
+                format!(
+                    "{}–{}",
+                    lesson.datetime.format("%H:%M"),
+                    lesson.end.format("%H:%M")
+                ),
+                or_absent(lesson.subject.as_deref()).to_string(),
+                or_absent(lesson.teacher.as_deref()).to_string(),
+                lesson.room_label().unwrap_or_else(|| ABSENT.to_string()),
+            ];
+            let mut x: u32 = PADDING;
+            for (cell, (_, column_width)) in cells.iter().zip(COLUMNS) {
+                self.draw_text(&mut pixmap, cell, x, y, column_width, TEXT_COLOR);
+                x += column_width;
+            }
+            if cancelled {
+                strike_through(&mut pixmap, PADDING, width - PADDING, y + ROW_HEIGHT / 2);
+            }
+            y += ROW_HEIGHT;
+        }
+
+        pixmap
+            .encode_png()
+            .context("Could not encode timetable image")
+    }
+
+    /// Draw the text vertically centered in the cell at `(x, y)`, cut off at its width.
+    fn draw_text(&self, pixmap: &mut Pixmap, text: &str, x: u32, y: u32, width: u32, rgb: [u8; 3]) {
+        let font = self.font.as_scaled(PxScale::from(FONT_SIZE));
+        let baseline: f32 = y as f32 + (ROW_HEIGHT as f32 + font.ascent() + font.descent()) / 2.0;
+        let max_x: f32 = (x + width) as f32 - 6.0;
+        let mut caret: f32 = x as f32 + 6.0;
+
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            let advance: f32 = font.h_advance(id);
+            if caret + advance > max_x {
+                break;
+            }
+            let glyph = id.with_scale_and_position(FONT_SIZE, point(caret, baseline));
+            caret += advance;
+            let Some(outlined) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                blend_pixel(pixmap, px, py, rgb, coverage);
+            });
+        }
+    }
+}
+
+/// Mix the color into the (opaque) pixel by the given coverage.
+fn blend_pixel(pixmap: &mut Pixmap, x: i32, y: i32, rgb: [u8; 3], coverage: f32) {
+    let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+        return;
+    };
+    if x >= pixmap.width() || y >= pixmap.height() {
+        return;
+    }
+    let index = (y * pixmap.width() + x) as usize;
+    let pixel: &mut PremultipliedColorU8 = &mut pixmap.pixels_mut()[index];
+    let coverage: f32 = coverage.clamp(0.0, 1.0);
+    let mix = |old: u8, new: u8| -> u8 {
+        f32::from(old)
+            .mul_add(1.0 - coverage, f32::from(new) * coverage)
+            .round() as u8
+    };
+    let [r, g, b] = rgb;
+    if let Some(color) = PremultipliedColorU8::from_rgba(
+        mix(pixel.red(), r),
+        mix(pixel.green(), g),
+        mix(pixel.blue(), b),
+        255,
+    ) {
+        *pixel = color;
+    }
+}
+
+fn fill_rect(pixmap: &mut Pixmap, x: u32, y: u32, width: u32, height: u32, rgb: [u8; 3]) {
+    let Some(rect) = Rect::from_xywh(x as f32, y as f32, width as f32, height as f32) else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(rgb[0], rgb[1], rgb[2], 255);
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+}
+
+fn strike_through(pixmap: &mut Pixmap, from_x: u32, to_x: u32, y: u32) {
+    let mut path = PathBuilder::new();
+    path.move_to(from_x as f32, y as f32);
+    path.line_to(to_x as f32, y as f32);
+    let Some(path) = path.finish() else {
+        return;
+    };
+    let mut paint = Paint::default();
+    let [r, g, b] = TEXT_COLOR;
+    paint.set_color_rgba8(r, g, b, 255);
+    paint.anti_alias = true;
+    let stroke = Stroke {
+        width: 2.0,
+        ..Stroke::default()
+    };
+    pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+}
@@ -0,0 +1,46 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use reqwest::{Certificate, blocking::ClientBuilder};
+
+/// Extra TLS settings applied to every HTTP client (Untis and Discord alike).
+///
+/// Needed on networks that intercept TLS with their own certificate authority.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    root_certs: Vec<Certificate>,
+    accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust the PEM-encoded root certificate in the given file, in addition to the built-in ones.
+    pub fn root_cert_file(mut self, path: &Path) -> Result<Self> {
+        let pem = fs::read(path)
+            .with_context(|| format!("Could not read certificate file {}", path.display()))?;
+        let cert = Certificate::from_pem(&pem)
+            .with_context(|| format!("Invalid PEM certificate in {}", path.display()))?;
+        self.root_certs.push(cert);
+        Ok(self)
+    }
+
+    /// Accept any server certificate, even invalid or expired ones.
+    ///
+    /// This makes the connection vulnerable to interception; only use it as a last resort.
+    #[must_use]
+    pub fn danger_accept_invalid_certs(mut self) -> Self {
+        log::warn!("TLS certificate validation is DISABLED; connections can be intercepted!");
+        self.accept_invalid_certs = true;
+        self
+    }
+
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder
+            .tls_certs_merge(self.root_certs.clone())
+            .tls_danger_accept_invalid_certs(self.accept_invalid_certs)
+    }
+}
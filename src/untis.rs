@@ -1,18 +1,34 @@
+use std::{fmt, sync::Mutex};
+
 use anyhow::{Context, Result, bail};
 use reqwest::blocking::{Client, Response};
 use reqwest::{StatusCode, Url};
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 
+mod clock;
 pub mod entries;
+pub mod exams;
+mod host;
 mod login;
+pub mod messages;
+pub mod resources;
+mod school;
+pub mod time_grid;
+mod token;
+
+pub use host::HostAllowlist;
 
 use crate::json_util::improve_json_error;
+use crate::untis::clock::ClockSkew;
+use crate::untis::token::TokenStore;
 
 pub struct UntisClient {
     http_client: Client,
-    token: String,
+    token: TokenStore,
     base_url: Url,
+    clock_skew: ClockSkew,
+    format_version: Mutex<Option<i32>>,
 }
 
 impl UntisClient {
@@ -23,13 +39,26 @@ impl UntisClient {
             .join(relative_url)
             .context("Could not create URL")?;
         let ctx = || format!("Could not send GET request to {url}");
-        let resp: Response = self
-            .http_client
-            .get(url.clone())
-            .bearer_auth(&self.token)
-            .query(query)
-            .send()
-            .with_context(ctx)?;
+        let send = |token: &str| {
+            self.http_client
+                .get(url.clone())
+                .bearer_auth(token)
+                .query(query)
+                .send()
+                .with_context(ctx)
+        };
+
+        let token: String = self.token.get();
+        let mut resp: Response = send(&token)?;
+        if resp.status() == StatusCode::UNAUTHORIZED {
+            let token: String = self
+                .token
+                .refresh(&self.http_client, &token)
+                .context("Could not refresh Untis token")?;
+            resp = send(&token)?;
+        }
+        self.clock_skew.record(&resp);
+
         let text: String = handle_response(resp).with_context(ctx)?;
         Ok(text)
     }
@@ -40,13 +69,41 @@ impl UntisClient {
         J: DeserializeOwned,
     {
         let text: String = self.get(url, query)?;
-        let json: J = serde_json::from_str(&text)
-            .map_err(|e| improve_json_error(&e, &text))
-            .with_context(|| {
-                format!("Could not extract JSON from success response from GET request to {url}")
-            })?;
-        Ok(json)
+        parse_json(url, &text)
+    }
+}
+
+/// WebUntis answered with an HTML page instead of JSON, as it does while in maintenance.
+///
+/// This usually goes away by itself, so callers may treat it as transient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Maintenance;
+
+impl fmt::Display for Maintenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WebUntis appears to be in maintenance (returned HTML instead of JSON)")
+    }
+}
+
+impl std::error::Error for Maintenance {}
+
+/// Parses the text of a success response from a GET request to the relative URL.
+///
+/// An HTML page instead fails with [`Maintenance`].
+fn parse_json<J>(url: &str, text: &str) -> Result<J>
+where
+    J: DeserializeOwned,
+{
+    if text.trim_start().starts_with('<') {
+        return Err(anyhow::Error::new(Maintenance))
+            .with_context(|| format!("Unexpected response from GET request to {url}"));
     }
+    let json: J = serde_json::from_str(text)
+        .map_err(|e| improve_json_error(&e, text))
+        .with_context(|| {
+            format!("Could not extract JSON from success response from GET request to {url}")
+        })?;
+    Ok(json)
 }
 
 #[derive(Deserialize)]
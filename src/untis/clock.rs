@@ -0,0 +1,52 @@
+use std::sync::{Mutex, PoisonError};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{blocking::Response, header::DATE};
+
+use crate::untis::UntisClient;
+
+/// How far the server's clock is ahead of the local one, as of the most recent response.
+///
+/// Derived from the `Date` response header, so it is only accurate to about a second.
+pub struct ClockSkew {
+    skew: Mutex<Option<TimeDelta>>,
+}
+
+impl ClockSkew {
+    pub const fn new() -> Self {
+        Self {
+            skew: Mutex::new(None),
+        }
+    }
+
+    /// Compare the response's `Date` header against the local clock, if there is one.
+    pub fn record(&self, response: &Response) {
+        let Some(header) = response.headers().get(DATE) else {
+            return;
+        };
+        let Some(server_time) = header
+            .to_str()
+            .ok()
+            .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+        else {
+            log::debug!("Could not parse Date response header {header:?}");
+            return;
+        };
+        let skew: TimeDelta = server_time.with_timezone(&Utc) - Utc::now();
+        *self.skew.lock().unwrap_or_else(PoisonError::into_inner) = Some(skew);
+    }
+
+    fn get(&self) -> Option<TimeDelta> {
+        *self.skew.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl UntisClient {
+    /// How far the Untis server's clock is ahead of the local clock (negative if behind).
+    ///
+    /// Returns `None` until a response with a valid `Date` header has been received.
+    #[must_use]
+    pub fn clock_skew(&self) -> Option<TimeDelta> {
+        self.clock_skew.get()
+    }
+}
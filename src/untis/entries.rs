@@ -1,34 +1,78 @@
 mod row;
 
+use std::env;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::{OnceLock, PoisonError};
+use std::thread;
 
 use anyhow::{Context, Result, bail};
-use chrono::{NaiveDate, NaiveDateTime};
-use serde::{Deserialize, Deserializer};
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-use crate::json_util::{parse_datetime, parse_string, parse_vec};
-use crate::untis::UntisClient;
+use crate::json_util::{
+    improve_json_error, parse_datetime, parse_opt_scalar, parse_string, parse_vec,
+};
+use crate::untis::{UntisClient, parse_json};
+
+/// The version of the entries format this crate understands.
+///
+/// Responses in other versions are still parsed on a best-effort basis.
+pub const FORMAT_VERSION: i32 = 19;
+
+/// Environment variable overriding [`FORMAT_VERSION`], to keep running through a format bump
+/// until this crate is updated.
+pub const FORMAT_VERSION_ENV: &str = "WEBUNTIS_FORMAT_VERSION";
+
+/// The format version to request and expect: [`FORMAT_VERSION`], unless overridden through
+/// [`FORMAT_VERSION_ENV`].
+pub fn expected_format_version() -> i32 {
+    static VERSION: OnceLock<i32> = OnceLock::new();
+    *VERSION.get_or_init(|| {
+        let Ok(value) = env::var(FORMAT_VERSION_ENV) else {
+            return FORMAT_VERSION;
+        };
+        match value.trim().parse() {
+            Ok(version) => {
+                log::warn!(
+                    "!!! Overriding the entries format version with {version} \
+                     (instead of {FORMAT_VERSION}) because {FORMAT_VERSION_ENV} is set; \
+                     responses may not be understood correctly !!!"
+                );
+                version
+            }
+            Err(e) => {
+                log::error!(
+                    "Ignoring invalid {FORMAT_VERSION_ENV} {value:?} ({e}); \
+                     using format version {FORMAT_VERSION}"
+                );
+                FORMAT_VERSION
+            }
+        }
+    })
+}
 
-// The format version has a custom deserializer to catch errors early in case of format update.
-const FORMAT_VERSION: i32 = 19;
+/// Only the format version, to find out about format updates even if parsing the rest fails.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct EntriesFormat {
+    format: i32,
+}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct FormatVersion;
-
-impl<'de> Deserialize<'de> for FormatVersion {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let version = i32::deserialize(deserializer)?;
-        if version != FORMAT_VERSION {
-            return Err(serde::de::Error::custom(format!(
-                "Format Version mismatch: expected {FORMAT_VERSION}, got {version} (contact project maintainers)"
-            )));
-        }
-        Ok(Self)
+/// Parses a `timetable/entries` response body without fetching it, e.g. one saved for a bug
+/// report.
+///
+/// # Errors
+/// If the JSON does not match the expected format or the response lists API errors.
+pub fn parse_entries(text: &str) -> Result<Vec<Day>> {
+    let entries: Entries = serde_json::from_str(text)
+        .map_err(|e| improve_json_error(&e, text))
+        .context("Could not parse timetable entries")?;
+    if !entries.errors.is_empty() {
+        bail!("Response lists API errors: {:?}", entries.errors);
     }
+    Ok(entries.days)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
@@ -40,10 +84,26 @@ pub struct Duration {
     pub end: NaiveDateTime,
 }
 
+/// How many days [`UntisClient::fetch_entries_chunked`] fetches per request.
+const CHUNK_DAYS: u64 = 7;
+/// How often [`UntisClient::fetch_entries_chunked`] tries each chunk.
+pub const CHUNK_ATTEMPTS: u32 = 2;
+const CHUNK_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The result of [`UntisClient::fetch_entries_chunked`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkedEntries {
+    /// All days that could be fetched, in order.
+    pub days: Vec<Day>,
+    /// The (inclusive) date ranges that could not be fetched.
+    pub failed: Vec<(NaiveDate, NaiveDate)>,
+}
+
+/// A whole `timetable/entries` response.
+///
+/// The endpoint does not paginate: every day of the requested range is in this one response.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 struct Entries {
-    #[allow(unused)]
-    format: FormatVersion,
     days: Vec<Day>,
     errors: Vec<JsonValue>,
 }
@@ -114,16 +174,33 @@ pub struct Row {
 
     #[serde(deserialize_with = "parse_string")]
     pub display_name: String,
+
+    /// Extra room metadata, only provided by some instances.
+    #[serde(default, deserialize_with = "parse_opt_scalar")]
+    pub building: Option<String>,
+
+    #[serde(default, deserialize_with = "parse_opt_scalar")]
+    pub floor: Option<String>,
+
+    #[serde(default)]
+    pub capacity: Option<u32>,
+
+    /// The colors the school assigned (e.g. to a subject), as `#RRGGBB`, if any.
+    #[serde(default, deserialize_with = "parse_opt_scalar")]
+    pub back_color: Option<String>,
+
+    #[serde(default, deserialize_with = "parse_opt_scalar")]
+    pub fore_color: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EntryText {
     #[serde(rename = "type")]
     pub text_type: EntryTextType,
     pub text: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EntryType {
     NormalTeachingPeriod,
@@ -131,7 +208,18 @@ pub enum EntryType {
     Event,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+impl fmt::Display for EntryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            Self::NormalTeachingPeriod => "Lesson",
+            Self::Exam => "Exam",
+            Self::Event => "Event",
+        };
+        f.write_str(string)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Status {
     NoData,
@@ -148,6 +236,43 @@ impl Status {
     pub const fn is_normal(self) -> bool {
         matches!(self, Self::NoData | Self::NotAllowed | Self::Regular)
     }
+
+    /// Whether entering (or leaving) this status warrants a notification.
+    #[must_use]
+    pub const fn is_change_worthy(self) -> bool {
+        !self.is_normal()
+    }
+
+    #[must_use]
+    pub const fn is_cancellation(self) -> bool {
+        matches!(self, Self::Cancelled | Self::Removed)
+    }
+}
+
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    /// Parses a status case-insensitively, ignoring `_`, `-` and spaces (e.g. `no-data`).
+    fn from_str(string: &str) -> Result<Self> {
+        let normalized: String = string
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        Ok(match normalized.as_str() {
+            "nodata" => Self::NoData,
+            "notallowed" => Self::NotAllowed,
+            "regular" => Self::Regular,
+            "added" => Self::Added,
+            "changed" => Self::Changed,
+            "removed" => Self::Removed,
+            "cancelled" => Self::Cancelled,
+            _ => bail!(
+                "Unknown status {string:?}; expected no-data, not-allowed, regular, added, \
+                 changed, removed or cancelled"
+            ),
+        })
+    }
 }
 
 impl fmt::Display for Status {
@@ -165,13 +290,23 @@ impl fmt::Display for Status {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EntryTextType {
     LessonInfo,
     SubstitutionText,
 }
 
+impl fmt::Display for EntryTextType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            Self::LessonInfo => "Lesson Info",
+            Self::SubstitutionText => "Substitution Text",
+        };
+        f.write_str(string)
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RowType {
@@ -179,6 +314,77 @@ pub enum RowType {
     Teacher,
     Room,
     Info,
+    /// Takes the place of the teacher in a teacher's timetable.
+    Class,
+    /// The part of a split class attending, e.g. a course or language group.
+    StudentGroup,
+}
+
+/// What kind of timetable is fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResourceType {
+    #[default]
+    Class,
+    Teacher,
+    Room,
+    Student,
+}
+
+/// A timetable to fetch: the kind of resource together with its ID, so the two can't be
+/// mismatched (e.g. a student ID queried as a class).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timetable {
+    pub kind: ResourceType,
+    pub id: i32,
+}
+
+impl Timetable {
+    #[must_use]
+    pub const fn class(id: i32) -> Self {
+        Self {
+            kind: ResourceType::Class,
+            id,
+        }
+    }
+}
+
+impl FromStr for ResourceType {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        Ok(match string.to_ascii_lowercase().as_str() {
+            "class" => Self::Class,
+            "teacher" => Self::Teacher,
+            "room" => Self::Room,
+            "student" => Self::Student,
+            _ => {
+                bail!("Unknown resource type {string:?}; expected class, teacher, room or student")
+            }
+        })
+    }
+}
+
+impl ResourceType {
+    /// The name used for the `resourceType` query parameter.
+    pub(crate) const fn query_name(self) -> &'static str {
+        match self {
+            Self::Class => "CLASS",
+            Self::Teacher => "TEACHER",
+            Self::Room => "ROOM",
+            Self::Student => "STUDENT",
+        }
+    }
+
+    /// The element type used by the WebUntis web client.
+    const fn element_type(self) -> u8 {
+        match self {
+            Self::Class => 1,
+            Self::Teacher => 2,
+            Self::Room => 4,
+            Self::Student => 5,
+        }
+    }
 }
 
 impl UntisClient {
@@ -196,17 +402,34 @@ impl UntisClient {
         &self,
         start: NaiveDate,
         end: NaiveDate,
-        timetable_id: i32,
+        timetable: Timetable,
     ) -> Result<Vec<Day>> {
         let query: &[(&str, &str)] = &[
             ("start", &start.to_string()),
             ("end", &end.to_string()),
-            ("resourceType", "CLASS"), // can be changed to STUDENT
-            ("resources", &timetable_id.to_string()),
-            ("format", &FORMAT_VERSION.to_string()),
+            ("resourceType", timetable.kind.query_name()),
+            ("resources", &timetable.id.to_string()),
+            ("format", &expected_format_version().to_string()),
         ];
 
-        let entries: Entries = self.get_json("timetable/entries", query)?;
+        let url = "timetable/entries";
+        let text: String = self.get(url, query)?;
+        // Responses are gzip-compressed in transfer, so this is the size after decompression.
+        log::debug!(
+            "Fetched {} bytes of entries for {start} to {end}",
+            text.len()
+        );
+        if let Ok(EntriesFormat { format }) = serde_json::from_str(&text) {
+            let expected = expected_format_version();
+            if format != expected {
+                log::warn!("Entries are in format version {format} instead of {expected}");
+            }
+            *self
+                .format_version
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(format);
+        }
+        let entries: Entries = parse_json(url, &text)?;
 
         if !entries.errors.is_empty() {
             bail!("API returned errors: {:?}", entries.errors);
@@ -215,14 +438,161 @@ impl UntisClient {
         Ok(entries.days)
     }
 
-    pub fn fetch_single_entry(&self, date: NaiveDate, timetable_id: i32) -> Result<Day> {
+    /// A link to the timetable of the given date in the WebUntis web client.
+    ///
+    /// # Errors
+    /// Only if the school's URL is malformed, which logging in already rules out.
+    pub fn timetable_web_url(&self, timetable: Timetable, date: NaiveDate) -> Result<Url> {
+        let mut url: Url = self.base_url.join("/WebUntis/")?;
+        let Timetable { kind, id } = timetable;
+        let element_type = kind.element_type();
+        url.set_fragment(Some(&format!(
+            "/basic/timetable?type={element_type}&id={id}&date={date}"
+        )));
+        Ok(url)
+    }
+
+    /// The format version of the most recently fetched entries, if any were fetched yet.
+    ///
+    /// Compare against [`expected_format_version`] to find out whether the API format was updated.
+    #[must_use]
+    pub fn format_version(&self) -> Option<i32> {
+        *self
+            .format_version
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Like [`Self::fetch_entries`], but fetches a wide range in chunks of a week, each tried
+    /// up to [`CHUNK_ATTEMPTS`] times.
+    ///
+    /// Chunks that still fail are logged and listed in [`ChunkedEntries::failed`] instead of
+    /// discarding the days that could be fetched.
+    #[must_use]
+    pub fn fetch_entries_chunked(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        timetable: Timetable,
+    ) -> ChunkedEntries {
+        let mut entries = ChunkedEntries {
+            days: Vec::new(),
+            failed: Vec::new(),
+        };
+        let mut chunk_start = start;
+        while chunk_start <= end {
+            let chunk_end = chunk_start
+                .checked_add_days(Days::new(CHUNK_DAYS - 1))
+                .map_or(end, |date| date.min(end));
+            match self.fetch_chunk(chunk_start, chunk_end, timetable) {
+                Ok(days) => entries.days.extend(days),
+                Err(e) => {
+                    log::warn!("Could not fetch entries from {chunk_start} to {chunk_end}: {e:?}");
+                    entries.failed.push((chunk_start, chunk_end));
+                }
+            }
+            let Some(next) = chunk_end.succ_opt() else {
+                break;
+            };
+            chunk_start = next;
+        }
+        entries
+    }
+
+    fn fetch_chunk(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        timetable: Timetable,
+    ) -> Result<Vec<Day>> {
+        let mut attempt: u32 = 1;
+        loop {
+            match self.fetch_entries(start, end, timetable) {
+                Ok(days) => return Ok(days),
+                Err(e) if attempt < CHUNK_ATTEMPTS => {
+                    log::debug!(
+                        "Fetching {start} to {end} failed (attempt {attempt}); retrying: {e}"
+                    );
+                    thread::sleep(CHUNK_RETRY_DELAY);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Fetch a single day; if the API returns no day at all, an empty day is returned.
+    pub fn fetch_single_entry(&self, date: NaiveDate, timetable: Timetable) -> Result<Day> {
         let days: Vec<Day> = self
-            .fetch_entries(date, date, timetable_id)
+            .fetch_entries(date, date, timetable)
             .context("Could not fetch timetable entry")?;
+        single_day(date, days)
+    }
+}
 
-        match days.as_slice() {
-            [day] => Ok(day.clone()),
-            _ => bail!("API returned {} days instead of just one", days.len()),
+/// The only day of a single-day response, or an empty day if the response has none.
+fn single_day(date: NaiveDate, days: Vec<Day>) -> Result<Day> {
+    let count = days.len();
+    let mut days = days.into_iter();
+    match (days.next(), days.next()) {
+        (Some(day), None) => Ok(day),
+        // No timetable published for the date (yet); the same as a day without lessons.
+        (None, _) => Ok(Day {
+            date,
+            status: Status::NoData,
+            grid_entries: Vec::new(),
+        }),
+        (Some(_), Some(_)) => bail!("API returned {count} days instead of just one"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_non_normal_statuses_are_change_worthy() {
+        for status in [Status::NoData, Status::NotAllowed, Status::Regular] {
+            assert!(!status.is_change_worthy(), "{status:?}");
+        }
+        for status in [
+            Status::Added,
+            Status::Changed,
+            Status::Removed,
+            Status::Cancelled,
+        ] {
+            assert!(status.is_change_worthy(), "{status:?}");
+        }
+    }
+
+    fn day(date: NaiveDate) -> Day {
+        Day {
+            date,
+            status: Status::Regular,
+            grid_entries: Vec::new(),
         }
     }
+
+    #[test]
+    fn single_day_without_days_is_empty() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let day: Day = single_day(date, Vec::new()).unwrap();
+        assert_eq!(day.date, date);
+        assert_eq!(day.status, Status::NoData);
+        assert!(day.grid_entries.is_empty());
+    }
+
+    #[test]
+    fn single_day_returns_the_only_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        assert_eq!(single_day(date, vec![day(date)]).unwrap(), day(date));
+    }
+
+    #[test]
+    fn single_day_rejects_several_days() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let next = date.succ_opt().unwrap();
+        let e = single_day(date, vec![day(date), day(next)]).unwrap_err();
+        assert_eq!(e.to_string(), "API returned 2 days instead of just one");
+    }
 }
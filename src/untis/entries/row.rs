@@ -2,45 +2,143 @@ use anyhow::{Result, bail};
 
 use crate::untis::entries::{GridEntry, Row, RowType, RowWrapper};
 
+impl Row {
+    /// Where a room is (building, floor) and how big it is, if the instance provides that.
+    #[must_use]
+    pub fn location(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            self.building.clone(),
+            self.floor.as_ref().map(|x| format!("floor {x}")),
+            self.capacity.map(|x| format!("{x} seats")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
 impl GridEntry {
     pub fn info_maybe_removed(&self) -> Result<(&Row, bool)> {
-        extract_one_with_type(&self.position1, RowType::Info)
+        self.extract_one_with_type(RowType::Info)
     }
 
     pub fn info(&self) -> Result<&Row> {
         ensure_not_removed(self.info_maybe_removed()?)
     }
 
+    /// The (not removed) info row, e.g. the class or group of a lesson.
+    #[must_use]
+    pub fn info_opt(&self) -> Option<&Row> {
+        self.rows()
+            .filter_map(|wrapper| wrapper.current.as_ref())
+            .find(|row| row.row_type == RowType::Info)
+    }
+
     pub fn subject_maybe_removed(&self) -> Result<(&Row, bool)> {
-        extract_one_with_type(&self.position1, RowType::Subject)
+        self.extract_one_with_type(RowType::Subject)
     }
 
     pub fn subject(&self) -> Result<&Row> {
         ensure_not_removed(self.subject_maybe_removed()?)
     }
 
+    /// Like [`Self::subject`], but returns `None` if there is no subject row at all.
+    pub fn subject_opt(&self) -> Result<Option<&Row>> {
+        self.extract_optional_with_type(RowType::Subject)?
+            .map(ensure_not_removed)
+            .transpose()
+    }
+
+    /// The (not removed) classes attending, as listed in a teacher's timetable.
+    #[must_use]
+    pub fn classes(&self) -> Vec<&Row> {
+        self.rows()
+            .filter_map(|wrapper| wrapper.current.as_ref())
+            .filter(|row| row.row_type == RowType::Class)
+            .collect()
+    }
+
+    /// The (not removed) student groups attending, if only part of the class is.
+    #[must_use]
+    pub fn student_groups(&self) -> Vec<&Row> {
+        self.rows()
+            .filter_map(|wrapper| wrapper.current.as_ref())
+            .filter(|row| row.row_type == RowType::StudentGroup)
+            .collect()
+    }
+
     pub fn teacher_maybe_removed(&self) -> Result<(&Row, bool)> {
-        extract_one_with_type(&self.position2, RowType::Teacher)
+        self.extract_one_with_type(RowType::Teacher)
     }
 
     pub fn teacher(&self) -> Result<&Row> {
         ensure_not_removed(self.teacher_maybe_removed()?)
     }
 
+    /// Like [`Self::teacher_maybe_removed`], but returns `None` if there is no teacher row at all.
+    pub fn teacher_maybe_removed_opt(&self) -> Result<Option<(&Row, bool)>> {
+        self.extract_optional_with_type(RowType::Teacher)
+    }
+
     pub fn room_maybe_removed(&self) -> Result<(&Row, bool)> {
-        extract_one_with_type(&self.position3, RowType::Room)
+        self.extract_one_with_type(RowType::Room)
     }
 
     pub fn room(&self) -> Result<&Row> {
         ensure_not_removed(self.room_maybe_removed()?)
     }
+
+    /// Like [`Self::room`], but returns `None` if there is no room row at all.
+    pub fn room_opt(&self) -> Result<Option<&Row>> {
+        self.extract_optional_with_type(RowType::Room)?
+            .map(ensure_not_removed)
+            .transpose()
+    }
+
+    /// Whether there is a row of the given type (current or removed) in any position.
+    #[must_use]
+    pub fn has_row(&self, row_type: RowType) -> bool {
+        !self.rows_with_type(row_type).is_empty()
+    }
+
+    /// The rows of all positions.
+    ///
+    /// Usually, position 1 holds the subject (or info), 2 the teacher and 3 the room, but not
+    /// every school's grid is laid out like that, so rows are looked up by type instead.
+    fn rows(&self) -> impl Iterator<Item = &RowWrapper> {
+        [&self.position1, &self.position2, &self.position3]
+            .into_iter()
+            .flatten()
+    }
+
+    fn rows_with_type(&self, row_type: RowType) -> Vec<&RowWrapper> {
+        self.rows()
+            .filter(|wrapper| {
+                extract_row_with_status(wrapper).is_ok_and(|(row, _)| row.row_type == row_type)
+            })
+            .collect()
+    }
+
+    /// The one row of the expected type across all positions.
+    fn extract_one_with_type(&self, expected_type: RowType) -> Result<(&Row, bool)> {
+        let wrapper = extract_one(&self.rows_with_type(expected_type), expected_type)?;
+        extract_row_with_status(wrapper)
+    }
+
+    fn extract_optional_with_type(&self, expected_type: RowType) -> Result<Option<(&Row, bool)>> {
+        if !self.has_row(expected_type) {
+            return Ok(None);
+        }
+        self.extract_one_with_type(expected_type).map(Some)
+    }
 }
 
-fn extract_one(position_n: &[RowWrapper]) -> Result<&RowWrapper> {
-    match position_n.len() {
-        0 => bail!("Row is empty"),
-        1 => Ok(&position_n[0]),
-        n => bail!("Row has {n} elements (expected exactly one)"),
+fn extract_one<'a>(rows: &[&'a RowWrapper], row_type: RowType) -> Result<&'a RowWrapper> {
+    match rows {
+        [] => bail!("No {row_type:?} row"),
+        [wrapper] => Ok(wrapper),
+        _ => bail!("{} {row_type:?} rows (expected exactly one)", rows.len()),
     }
 }
 
@@ -60,23 +158,3 @@ fn ensure_not_removed((row, is_removed): (&Row, bool)) -> Result<&Row> {
     }
     Ok(row)
 }
-
-fn assert_row_type(
-    (row, is_removed): (&Row, bool),
-    expected_type: RowType,
-) -> Result<(&Row, bool)> {
-    if row.row_type != expected_type {
-        bail!(
-            "Expected row type {:?} but got {:?}",
-            expected_type,
-            row.row_type
-        );
-    }
-    Ok((row, is_removed))
-}
-
-fn extract_one_with_type(position: &[RowWrapper], expected_type: RowType) -> Result<(&Row, bool)> {
-    let wrapper = extract_one(position)?;
-    let (row, is_removed) = extract_row_with_status(wrapper)?;
-    assert_row_type((row, is_removed), expected_type)
-}
@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::json_util::{parse_string, parse_vec};
+use crate::untis::UntisClient;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct ExamsResponse {
+    data: ExamsData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct ExamsData {
+    #[serde(deserialize_with = "parse_vec")]
+    exams: Vec<Exam>,
+}
+
+/// A scheduled exam, from the dedicated exams endpoint (which has more details than exam
+/// grid entries).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exam {
+    pub id: i32,
+
+    /// The kind of exam as configured by the school, e.g. `Klausur`.
+    #[serde(deserialize_with = "parse_string")]
+    pub exam_type: String,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub name: String,
+
+    /// Sent as a number like `20240510`.
+    #[serde(rename = "examDate", deserialize_with = "parse_untis_date")]
+    pub date: NaiveDate,
+
+    /// Sent as a number like `800` for 08:00.
+    #[serde(rename = "startTime", deserialize_with = "parse_untis_time")]
+    pub start: NaiveTime,
+
+    #[serde(rename = "endTime", deserialize_with = "parse_untis_time")]
+    pub end: NaiveTime,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub subject: String,
+
+    /// The short names of the supervising teachers.
+    #[serde(deserialize_with = "parse_vec")]
+    pub teachers: Vec<String>,
+
+    #[serde(deserialize_with = "parse_vec")]
+    pub rooms: Vec<String>,
+
+    /// The short names of the classes taking the exam.
+    #[serde(rename = "studentClass", deserialize_with = "parse_vec")]
+    pub classes: Vec<String>,
+
+    /// Free text, e.g. the topics covered.
+    #[serde(deserialize_with = "parse_string")]
+    pub text: String,
+}
+
+impl Exam {
+    /// The exam on a single line, e.g. `2024-05-10 08:00–09:30 Math (Klausur), R12`.
+    #[must_use]
+    pub fn summary_line(&self) -> String {
+        let mut line = format!(
+            "{} {}–{} {}",
+            self.date,
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            self.subject
+        );
+        if !self.exam_type.is_empty() {
+            line.push_str(&format!(" ({})", self.exam_type));
+        }
+        if !self.rooms.is_empty() {
+            line.push_str(&format!(", {}", self.rooms.join(", ")));
+        }
+        line
+    }
+}
+
+/// A difference between two lists of exams.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExamChange {
+    Added { exam: Exam },
+    Changed { old: Exam, exam: Exam },
+    Removed { exam: Exam },
+}
+
+impl ExamChange {
+    /// The (new) exam this change refers to.
+    #[must_use]
+    pub const fn exam(&self) -> &Exam {
+        match self {
+            Self::Added { exam } | Self::Changed { exam, .. } | Self::Removed { exam } => exam,
+        }
+    }
+
+    /// Update a list of exams with this change.
+    pub fn apply(&self, exams: &mut Vec<Exam>) {
+        match self {
+            Self::Added { exam } => exams.push(exam.clone()),
+            Self::Changed { exam, .. } => {
+                for old in exams.iter_mut().filter(|x| x.id == exam.id) {
+                    old.clone_from(exam);
+                }
+            }
+            Self::Removed { exam } => exams.retain(|x| x.id != exam.id),
+        }
+    }
+
+    #[must_use]
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Self::Added { .. } => "Exam Added",
+            Self::Changed { .. } => "Exam Changed",
+            Self::Removed { .. } => "Exam Removed",
+        }
+    }
+
+    #[must_use]
+    pub fn description(&self) -> String {
+        match self {
+            Self::Added { exam } | Self::Removed { exam } => exam.summary_line(),
+            Self::Changed { old, exam } => {
+                format!("{}\nbefore: {}", exam.summary_line(), old.summary_line())
+            }
+        }
+    }
+}
+
+/// Compares two lists of exams, matching them by ID.
+#[must_use]
+pub fn diff_exams(old: &[Exam], new: &[Exam]) -> Vec<ExamChange> {
+    let mut changes: Vec<ExamChange> = Vec::new();
+    for exam in new {
+        match old.iter().find(|x| x.id == exam.id) {
+            None => changes.push(ExamChange::Added { exam: exam.clone() }),
+            Some(old) if old != exam => changes.push(ExamChange::Changed {
+                old: old.clone(),
+                exam: exam.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for exam in old {
+        if !new.iter().any(|x| x.id == exam.id) {
+            changes.push(ExamChange::Removed { exam: exam.clone() });
+        }
+    }
+    changes
+}
+
+impl UntisClient {
+    /// Fetch the exams of a class between the given dates (inclusive).
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed
+    pub fn fetch_exams(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        class_id: i32,
+    ) -> Result<Vec<Exam>> {
+        let query: &[(&str, &str)] = &[
+            ("startDate", &start.format("%Y%m%d").to_string()),
+            ("endDate", &end.format("%Y%m%d").to_string()),
+            ("klasseId", &class_id.to_string()),
+            ("withGrades", "false"),
+        ];
+        let response: ExamsResponse = self
+            .get_json("/WebUntis/api/exams", query)
+            .context("Could not fetch exams")?;
+        Ok(response.data.exams)
+    }
+}
+
+/// Deserializes a date sent as a number in the YYYYMMDD format.
+fn parse_untis_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n = u32::deserialize(deserializer)?;
+    NaiveDate::parse_from_str(&n.to_string(), "%Y%m%d").map_err(serde::de::Error::custom)
+}
+
+/// Deserializes a time sent as a number in the hmm format (e.g. `745` for 07:45).
+fn parse_untis_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n = u32::deserialize(deserializer)?;
+    NaiveTime::from_hms_opt(n / 100, n % 100, 0)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid time {n}")))
+}
@@ -0,0 +1,52 @@
+use anyhow::{Context, Result, ensure};
+use reqwest::Url;
+
+/// Host suffixes the Untis API may be reached under, so a mistyped or malicious server
+/// doesn't receive the credentials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostAllowlist {
+    suffixes: Vec<String>,
+}
+
+impl HostAllowlist {
+    pub const DEFAULT_SUFFIX: &str = "webuntis.com";
+
+    /// Allow the given host suffixes (e.g. `webuntis.com` allows `neilo.webuntis.com`).
+    #[must_use]
+    pub fn new(suffixes: Vec<String>) -> Self {
+        let suffixes = suffixes
+            .into_iter()
+            .map(|s| s.trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        Self { suffixes }
+    }
+
+    /// Make sure the URL points to an allowed host.
+    ///
+    /// # Errors
+    /// The URL has no host or its host does not end in any of the allowed suffixes.
+    pub fn check(&self, url: &Url) -> Result<()> {
+        let host: String = url
+            .host_str()
+            .with_context(|| format!("URL {url} has no host"))?
+            .to_ascii_lowercase();
+        let allowed: bool = self.suffixes.iter().any(|suffix| {
+            host == *suffix
+                || host
+                    .strip_suffix(suffix.as_str())
+                    .is_some_and(|rest| rest.ends_with('.'))
+        });
+        ensure!(
+            allowed,
+            "Untis host {host:?} is not in the allowed host suffixes {:?}",
+            self.suffixes
+        );
+        Ok(())
+    }
+}
+
+impl Default for HostAllowlist {
+    fn default() -> Self {
+        Self::new(vec![Self::DEFAULT_SUFFIX.to_owned()])
+    }
+}
@@ -1,16 +1,27 @@
-use anyhow::{Context, Result};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result, bail};
 use reqwest::{
-    Url,
+    StatusCode, Url,
     blocking::{Client, Response},
 };
 use serde::Serialize;
 
-use crate::{Credentials, untis::UntisClient, validate};
+use crate::{
+    Credentials, TlsConfig,
+    untis::clock::ClockSkew,
+    untis::token::{TokenStore, fetch_token},
+    untis::{HostAllowlist, UntisClient},
+    validate,
+};
 
 use super::handle_response;
 
 #[derive(Serialize)]
 struct AuthRequest<'a> {
+    /// Only needed on regional servers, where the school is not part of the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    school: Option<&'a str>,
     j_username: &'a str,
     j_password: &'a str,
 }
@@ -21,30 +32,55 @@ impl UntisClient {
     /// # Errors
     /// Possible failure reasons:
     /// * Invalid school name (subdomain)
+    /// * Server host not in the default [`HostAllowlist`]
     /// * Error sending HTTPS request
     /// * Invalid UTF-8 in response body
+    /// * Incorrect username or password (Untis answers with a HTTP redirect (302))
     /// * Response with non-success status code (not 2xx)
-    ///   > If your credentials are incorrect, it will return a HTTP redirect (302).
     /// * Invalid token
     pub fn login(credentials: &Credentials) -> Result<Self> {
+        Self::login_with_tls(
+            credentials,
+            &TlsConfig::default(),
+            &HostAllowlist::default(),
+        )
+    }
+
+    /// Like [`Self::login`], but with custom TLS settings for all requests of this session
+    /// and a custom allowlist for the server host.
+    ///
+    /// # Errors
+    /// See [`Self::login`].
+    pub fn login_with_tls(
+        credentials: &Credentials,
+        tls: &TlsConfig,
+        allowed_hosts: &HostAllowlist,
+    ) -> Result<Self> {
         let Credentials {
             school,
             username,
             password,
+            server,
         } = credentials;
         validate::school(school)?;
 
-        let base_url: String = format!("https://{school}.webuntis.com/WebUntis/");
+        let base_url: String = server.as_ref().map_or_else(
+            || format!("https://{school}.webuntis.com/WebUntis/"),
+            |server| format!("https://{server}/WebUntis/"),
+        );
         let base_url =
             Url::parse(&base_url).with_context(|| format!("Could not parse URL {base_url:?}"))?;
+        allowed_hosts.check(&base_url)?;
 
-        let client = Client::builder()
+        let client = tls
+            .apply(Client::builder())
             .redirect(reqwest::redirect::Policy::none())
             .cookie_store(true)
             .build()?;
 
         let url = base_url.join("j_spring_security_check")?;
         let body = AuthRequest {
+            school: server.as_ref().map(|_| school.as_str()),
             j_username: username,
             j_password: password,
         };
@@ -57,24 +93,47 @@ impl UntisClient {
             .send()
             .context("Could not send request to j_spring_security_check")?;
 
+        // Redirects are not followed, so bad credentials show up as the redirect to the login page.
+        if resp.status() == StatusCode::FOUND {
+            bail!("Login failed: incorrect username or password");
+        }
         handle_response(resp)?;
 
-        let url = base_url.join("api/token/new")?;
-        let resp: Response = client
-            .get(url)
-            .send()
-            .context("Could not send request to token/new")?;
-
-        let token: String =
-            handle_response(resp).context("Bad response for token generation request")?;
-        validate::untis_token(&token)?;
+        let token_url = base_url.join("api/token/new")?;
+        let token: String = fetch_token(&client, &token_url)?;
 
         let api_client = Self {
             http_client: client,
-            token,
+            token: TokenStore::new(token_url, token),
             base_url: base_url.join("api/rest/view/v1/")?,
+            clock_skew: ClockSkew::new(),
+            format_version: Mutex::new(None),
         };
 
         Ok(api_client)
     }
+
+    /// Log out, invalidating the session on the server.
+    ///
+    /// Some schools limit the number of concurrent sessions, so lingering ones can cause
+    /// login failures later on.
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Response with non-success status code (neither 2xx nor a redirect)
+    pub fn logout(self) -> Result<()> {
+        let url = self.base_url.join("/WebUntis/j_spring_security_logout")?;
+        let resp: Response = self
+            .http_client
+            .post(url)
+            .send()
+            .context("Could not send request to j_spring_security_logout")?;
+
+        // Logging out redirects back to the login page.
+        if resp.status().is_redirection() {
+            return Ok(());
+        }
+        handle_response(resp)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::json_util::{parse_string, parse_vec};
+use crate::untis::UntisClient;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct NewsResponse {
+    data: NewsData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewsData {
+    #[serde(deserialize_with = "parse_vec")]
+    messages_of_day: Vec<DayMessage>,
+}
+
+/// A school-wide announcement from the start page of WebUntis ("message of the day"), e.g. about
+/// a fire drill.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayMessage {
+    pub id: i32,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub subject: String,
+
+    /// The body as HTML; see [`Self::plain_text`].
+    #[serde(deserialize_with = "parse_string")]
+    pub text: String,
+}
+
+impl DayMessage {
+    /// The body without HTML tags, with line breaks and paragraphs as newlines.
+    #[must_use]
+    pub fn plain_text(&self) -> String {
+        let mut plain = String::with_capacity(self.text.len());
+        let mut rest: &str = &self.text;
+        while let Some(start) = rest.find('<') {
+            plain.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('>') else {
+                rest = &rest[start..];
+                break;
+            };
+            let tag: String = rest[start + 1..start + end].to_ascii_lowercase();
+            if tag.starts_with("br") || tag == "/p" || tag == "/div" || tag == "/li" {
+                plain.push('\n');
+            }
+            rest = &rest[start + end + 1..];
+        }
+        plain.push_str(rest);
+        let plain = plain
+            .replace("&nbsp;", " ")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&");
+        plain
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl UntisClient {
+    /// Fetch the messages of the day shown for the given date.
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed
+    pub fn fetch_messages(&self, date: NaiveDate) -> Result<Vec<DayMessage>> {
+        let query: &[(&str, &str)] = &[("date", &date.format("%Y%m%d").to_string())];
+        let response: NewsResponse = self
+            .get_json("/WebUntis/api/public/news/newsWidgetData", query)
+            .context("Could not fetch the messages of the day")?;
+        Ok(response.data.messages_of_day)
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::json_util::parse_string;
+use crate::untis::{UntisClient, entries::ResourceType};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct TimetableFilter {
+    #[serde(default)]
+    classes: Vec<ClassWrapper>,
+    #[serde(default)]
+    rooms: Vec<RoomWrapper>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct ClassWrapper {
+    class: Resource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct RoomWrapper {
+    room: Resource,
+}
+
+/// A timetable which can be fetched (e.g. a class).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    pub id: i32,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub short_name: String,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub long_name: String,
+
+    #[serde(deserialize_with = "parse_string")]
+    pub display_name: String,
+}
+
+impl Resource {
+    /// Resolve a class name to its timetable ID among the given resources.
+    ///
+    /// Matching is case-insensitive on both the short and the long name.
+    ///
+    /// # Errors
+    /// * No class matches the name (the error lists all available names)
+    /// * Multiple classes match the name (the error lists the candidates)
+    pub fn resolve(resources: &[Self], name: &str) -> Result<i32> {
+        let candidates: Vec<&Self> = resources.iter().filter(|x| x.matches(name)).collect();
+
+        match candidates.as_slice() {
+            [resource] => Ok(resource.id),
+            [] => bail!(
+                "No class named {name:?} found; available: {}",
+                format_names(resources.iter())
+            ),
+            _ => bail!(
+                "Class name {name:?} is ambiguous; candidates: {}",
+                format_names(candidates.into_iter())
+            ),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.short_name.eq_ignore_ascii_case(name) || self.long_name.eq_ignore_ascii_case(name)
+    }
+}
+
+impl UntisClient {
+    /// Fetch all class timetables visible to the logged-in user.
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed
+    pub fn list_timetables(&self) -> Result<Vec<Resource>> {
+        self.list_resources(ResourceType::Class)
+    }
+
+    /// Fetch all timetables of this kind visible to the logged-in user.
+    ///
+    /// Only classes and rooms can be listed.
+    ///
+    /// # Errors
+    /// * The kind can't be listed
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed
+    pub fn list_resources(&self, kind: ResourceType) -> Result<Vec<Resource>> {
+        if !matches!(kind, ResourceType::Class | ResourceType::Room) {
+            bail!("Listing {} timetables is not supported", kind.query_name());
+        }
+        let query: &[(&str, &str)] = &[
+            ("resourceType", kind.query_name()),
+            ("timetableType", "STANDARD"),
+        ];
+        let filter: TimetableFilter = self.get_json("timetable/filter", query)?;
+        Ok(match kind {
+            ResourceType::Room => filter.rooms.into_iter().map(|x| x.room).collect(),
+            _ => filter.classes.into_iter().map(|x| x.class).collect(),
+        })
+    }
+
+    /// Make sure the logged-in user may view timetables of this kind, so a missing permission
+    /// is reported up front instead of showing up as empty or failing fetches.
+    ///
+    /// # Errors
+    /// The timetable filter for the kind could not be fetched, most likely due to a missing
+    /// permission (students can usually only view their own and their class's timetables).
+    pub fn check_timetable_access(&self, kind: ResourceType) -> Result<()> {
+        let query: &[(&str, &str)] = &[
+            ("resourceType", kind.query_name()),
+            ("timetableType", "STANDARD"),
+        ];
+        let _: JsonValue = self.get_json("timetable/filter", query).with_context(|| {
+            let suggestion = match kind {
+                ResourceType::Student => "class",
+                _ => "student",
+            };
+            format!(
+                "Your account can't view {} timetables; try --resource-type {suggestion}",
+                kind.query_name()
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Resolve a class name to its timetable ID.
+    ///
+    /// See [`Resource::resolve`] for how names are matched.
+    ///
+    /// # Errors
+    /// * Fetching the resources list failed
+    /// * Resolving the name failed (see [`Resource::resolve`])
+    pub fn resolve_timetable(&self, name: &str) -> Result<i32> {
+        let resources: Vec<Resource> = self.list_timetables()?;
+        Resource::resolve(&resources, name)
+    }
+}
+
+fn format_names<'a>(resources: impl Iterator<Item = &'a Resource>) -> String {
+    resources
+        .map(|x| format!("{} ({})", x.short_name, x.long_name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
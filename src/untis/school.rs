@@ -0,0 +1,31 @@
+use anyhow::{Context, Result};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::untis::UntisClient;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct AppData {
+    tenant: Tenant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Tenant {
+    time_zone: String,
+}
+
+impl UntisClient {
+    /// Fetch the timezone configured for the school.
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed (e.g. the school has no timezone configured)
+    /// * The timezone is not a valid IANA timezone name
+    pub fn fetch_school_timezone(&self) -> Result<Tz> {
+        let data: AppData = self.get_json("app/data", &[])?;
+        let name: &str = &data.tenant.time_zone;
+        Tz::from_str_insensitive(name).with_context(|| format!("Invalid timezone {name:?}"))
+    }
+}
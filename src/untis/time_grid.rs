@@ -0,0 +1,60 @@
+use anyhow::Result;
+use chrono::NaiveTime;
+use serde::Deserialize;
+
+use crate::LessonInfo;
+use crate::json_util::parse_time;
+use crate::untis::UntisClient;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct TimeGridResponse {
+    units: Vec<TimeGridUnit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeGridUnit {
+    unit_of_day: u32,
+
+    #[serde(deserialize_with = "parse_time")]
+    start_time: NaiveTime,
+}
+
+/// The school's bell schedule, mapping lesson start times to period numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeGrid {
+    units: Vec<TimeGridUnit>,
+}
+
+impl TimeGrid {
+    /// The number of the period starting at the given time, if any.
+    #[must_use]
+    pub fn period_at(&self, start: NaiveTime) -> Option<u32> {
+        self.units
+            .iter()
+            .find(|x| x.start_time == start)
+            .map(|x| x.unit_of_day)
+    }
+
+    /// Fill in the period number of every lesson (if it starts at a period boundary).
+    pub fn assign_periods(&self, lessons: &mut [LessonInfo]) {
+        for lesson in lessons {
+            lesson.period = self.period_at(lesson.datetime.time());
+        }
+    }
+}
+
+impl UntisClient {
+    /// Fetch the school's bell schedule.
+    ///
+    /// # Errors
+    /// * Error sending HTTPS request
+    /// * Server responded with a non-success status code (not 2xx)
+    /// * Json deserialization failed
+    pub fn fetch_time_grid(&self) -> Result<TimeGrid> {
+        let response: TimeGridResponse = self.get_json("timegrid", &[])?;
+        Ok(TimeGrid {
+            units: response.units,
+        })
+    }
+}
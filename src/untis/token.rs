@@ -0,0 +1,86 @@
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use reqwest::Url;
+use reqwest::blocking::{Client, Response};
+
+use crate::validate;
+
+use super::handle_response;
+
+/// Don't hammer the token endpoint if refreshing just failed.
+const REFRESH_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The bearer token, shared between all requests of one [`super::UntisClient`].
+///
+/// Refreshing happens while holding the lock, so concurrent requests that got rejected with
+/// the same stale token trigger only a single refresh and then reuse its result.
+pub struct TokenStore {
+    url: Url,
+    state: Mutex<TokenState>,
+}
+
+struct TokenState {
+    token: String,
+    last_failed_refresh: Option<Instant>,
+}
+
+impl TokenStore {
+    pub const fn new(url: Url, token: String) -> Self {
+        let state = TokenState {
+            token,
+            last_failed_refresh: None,
+        };
+        Self {
+            url,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn get(&self) -> String {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.token.clone()
+    }
+
+    /// Replace the token `stale` with a fresh one, unless another request already did so.
+    // The lock is deliberately held during the request so that only one refresh runs at a time.
+    #[allow(clippy::significant_drop_tightening)]
+    pub fn refresh(&self, client: &Client, stale: &str) -> Result<String> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.token != stale {
+            return Ok(state.token.clone());
+        }
+        if let Some(failed) = state.last_failed_refresh
+            && failed.elapsed() < REFRESH_BACKOFF
+        {
+            bail!("Token refresh failed recently; backing off for {REFRESH_BACKOFF:?}");
+        }
+
+        log::info!("Refreshing Untis token");
+        match fetch_token(client, &self.url) {
+            Ok(token) => {
+                state.token.clone_from(&token);
+                state.last_failed_refresh = None;
+                Ok(token)
+            }
+            Err(e) => {
+                state.last_failed_refresh = Some(Instant::now());
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Request a new token for the session stored in the client's cookies.
+pub fn fetch_token(client: &Client, url: &Url) -> Result<String> {
+    let resp: Response = client
+        .get(url.clone())
+        .send()
+        .context("Could not send request to token/new")?;
+
+    let token: String =
+        handle_response(resp).context("Bad response for token generation request")?;
+    validate::untis_token(&token)?;
+    Ok(token)
+}
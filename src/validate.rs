@@ -8,6 +8,20 @@ pub fn school(school_name: &str) -> Result<()> {
 }
 
 pub fn untis_token(token: &str) -> Result<()> {
+    three_part_token(token)
+}
+
+/// A bot token as shown in Discord's developer portal (without the `Bot ` prefix).
+pub fn discord_bot_token(token: &str) -> Result<()> {
+    ensure!(
+        !token.starts_with("Bot "),
+        "Token must be given without the `Bot ` prefix"
+    );
+    three_part_token(token)
+}
+
+/// A token made of three dot-separated parts, like a JWT.
+fn three_part_token(token: &str) -> Result<()> {
     let mut parts = token.split('.');
     for _ in 0..3 {
         let part: &str = parts.next().context("Token has too few parts")?;
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use reqwest::{
+    IntoUrl, Url,
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::Serialize;
+
+use crate::{
+    LessonChange, LessonInfo, TlsConfig,
+    notify::{Notifier, NotifyError},
+    untis::{exams::ExamChange, messages::DayMessage},
+};
+
+/// The backend name reported in [`NotifyError`]s.
+const BACKEND: &str = "Webhook";
+
+/// Posts every notification as JSON to an arbitrary URL, e.g. a Home Assistant webhook.
+#[derive(Debug, Clone)]
+pub struct GenericWebhookClient {
+    http_client: Client,
+    url: Url,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Payload<'a> {
+    Change {
+        /// See [`LessonInfo::key`].
+        key: String,
+        kind: &'static str,
+        title: &'static str,
+        description: String,
+        datetime: NaiveDateTime,
+        subject: Option<&'a str>,
+        group: Option<&'a str>,
+        teacher: Option<&'a str>,
+        room: Option<&'a str>,
+        web_url: Option<&'a str>,
+    },
+    Error {
+        message: &'a str,
+    },
+    Warning {
+        message: &'a str,
+    },
+    Digest {
+        date: NaiveDate,
+        changes: Vec<String>,
+    },
+    Timetable {
+        date: NaiveDate,
+        lessons: &'a [LessonInfo],
+    },
+    Exam {
+        title: &'static str,
+        change: &'a ExamChange,
+    },
+    Published {
+        dates: &'a [NaiveDate],
+    },
+    Message {
+        id: i32,
+        subject: &'a str,
+        /// The body without HTML.
+        text: String,
+    },
+}
+
+impl GenericWebhookClient {
+    /// Create a client sending the given extra headers (e.g. `Authorization`) with every request.
+    ///
+    /// # Errors
+    /// * Invalid URL
+    /// * Invalid header name or value
+    pub fn new(
+        url: impl IntoUrl,
+        headers: &HashMap<String, String>,
+        tls: &TlsConfig,
+    ) -> Result<Self> {
+        let url = url.into_url().context("Invalid webhook URL")?;
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::try_from(name)
+                    .with_context(|| format!("Invalid header name {name:?}"))?;
+                let mut value = HeaderValue::try_from(value)
+                    .with_context(|| format!("Invalid value for header {name}"))?;
+                value.set_sensitive(true);
+                Ok((name, value))
+            })
+            .collect::<Result<HeaderMap>>()?;
+        let http_client = tls
+            .apply(Client::builder())
+            .default_headers(headers)
+            .build()
+            .context("Could not build HTTP client")?;
+        Ok(Self { http_client, url })
+    }
+
+    fn send(&self, payload: &Payload) -> Result<()> {
+        self.http_client
+            .post(self.url.clone())
+            .json(payload)
+            .send()
+            .context("Could not send webhook request")?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl Notifier for GenericWebhookClient {
+    fn send_change(&self, change: &LessonChange) -> Result<(), NotifyError> {
+        let lesson = change.lesson();
+        log::info!(
+            "Sending {} regarding lesson at {}",
+            change.kind(),
+            lesson.datetime
+        );
+        self.send(&Payload::Change {
+            key: lesson.key(),
+            kind: change.kind(),
+            title: change.title(),
+            description: change.description(),
+            datetime: lesson.datetime,
+            subject: lesson.subject.as_deref(),
+            group: lesson.group.as_deref(),
+            teacher: lesson.teacher.as_deref(),
+            room: lesson.room.as_deref(),
+            web_url: lesson.web_url.as_ref().map(Url::as_str),
+        })
+        .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+
+    fn send_error(&self, err_message: &str) {
+        log::error!("{err_message}");
+        let payload = Payload::Error {
+            message: err_message,
+        };
+        if let Err(e) = self.send(&payload) {
+            log::error!("Sending error message to webhook failed: {e}");
+        }
+    }
+
+    fn send_warning(&self, message: &str) {
+        log::warn!("{message}");
+        if let Err(e) = self.send(&Payload::Warning { message }) {
+            log::error!("Sending warning message to webhook failed: {e}");
+        }
+    }
+
+    fn send_digest(&self, date: NaiveDate, changes: &[LessonChange]) -> Result<(), NotifyError> {
+        log::info!("Sending digest of {} changes for {date}", changes.len());
+        self.send(&Payload::Digest {
+            date,
+            changes: changes.iter().map(LessonChange::summary_line).collect(),
+        })
+        .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+
+    fn send_timetable(&self, date: NaiveDate, lessons: &[LessonInfo]) -> Result<(), NotifyError> {
+        log::info!("Sending timetable of {} lessons for {date}", lessons.len());
+        self.send(&Payload::Timetable { date, lessons })
+            .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+
+    fn send_exam_change(&self, change: &ExamChange) -> Result<(), NotifyError> {
+        log::info!("Sending {} for {}", change.title(), change.exam().date);
+        self.send(&Payload::Exam {
+            title: change.title(),
+            change,
+        })
+        .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+
+    fn send_published(&self, dates: &[NaiveDate]) -> Result<(), NotifyError> {
+        log::info!("Sending newly published timetable for {} days", dates.len());
+        self.send(&Payload::Published { dates })
+            .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+
+    fn send_day_message(&self, message: &DayMessage) -> Result<(), NotifyError> {
+        log::info!("Sending message of the day {}", message.id);
+        self.send(&Payload::Message {
+            id: message.id,
+            subject: &message.subject,
+            text: message.plain_text(),
+        })
+        .map_err(|e| NotifyError::new(BACKEND, e))
+    }
+}